@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, Date32Array, Decimal128Array, DictionaryArray, Float32Array, Float64Array,
+    RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use sqllogictest_flightsql::column::{
+    ConvertOptions, cell_to_string, convert_batches, convert_batches_with_options,
+};
+
+const ROWS: usize = 100_000;
+
+fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+    let fields = columns
+        .iter()
+        .map(|(name, array)| Field::new(*name, array.data_type().clone(), true))
+        .collect::<Vec<_>>();
+    let arrays = columns.into_iter().map(|(_, array)| array).collect();
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).unwrap()
+}
+
+/// Long strings with some empty values.
+fn wide_string_batch() -> RecordBatch {
+    let values = (0..ROWS)
+        .map(|i| {
+            if i % 10 == 0 {
+                String::new()
+            } else {
+                format!("{i:08}-").repeat(16)
+            }
+        })
+        .collect::<Vec<_>>();
+    batch(vec![("s", Arc::new(StringArray::from(values)))])
+}
+
+fn float_batch() -> RecordBatch {
+    let f32s = (0..ROWS).map(|i| i as f32 / 7.0).collect::<Vec<_>>();
+    let f64s = (0..ROWS).map(|i| i as f64 / 3.0).collect::<Vec<_>>();
+    batch(vec![
+        ("f32", Arc::new(Float32Array::from(f32s))),
+        ("f64", Arc::new(Float64Array::from(f64s))),
+    ])
+}
+
+fn decimal_batch() -> RecordBatch {
+    let values = Decimal128Array::from_iter_values((0..ROWS).map(|i| i as i128 * 1_234_567))
+        .with_precision_and_scale(38, 10)
+        .unwrap();
+    batch(vec![("d", Arc::new(values))])
+}
+
+fn dictionary_batch() -> RecordBatch {
+    let values = StringArray::from(vec!["alpha", "beta", "gamma", "delta"]);
+    let keys = (0..ROWS as i32).map(|i| i % 4).collect();
+    let array = DictionaryArray::<Int32Type>::try_new(keys, Arc::new(values)).unwrap();
+    let schema = Schema::new(vec![Field::new(
+        "d",
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        false,
+    )]);
+    RecordBatch::try_new(Arc::new(schema), vec![Arc::new(array)]).unwrap()
+}
+
+fn temporal_batch() -> RecordBatch {
+    let dates = Date32Array::from_iter_values((0..ROWS as i32).map(|i| i % 20_000));
+    let timestamps = TimestampMicrosecondArray::from_iter_values(
+        (0..ROWS as i64).map(|i| 1_700_000_000_000_000 + i * 1_000_003),
+    )
+    .with_timezone("UTC");
+    batch(vec![
+        ("date", Arc::new(dates)),
+        ("ts", Arc::new(timestamps)),
+    ])
+}
+
+fn batches() -> Vec<(&'static str, RecordBatch)> {
+    vec![
+        ("wide_string", wide_string_batch()),
+        ("float", float_batch()),
+        ("decimal", decimal_batch()),
+        ("dictionary", dictionary_batch()),
+        ("temporal", temporal_batch()),
+    ]
+}
+
+fn bench_convert_batches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert_batches");
+    for (name, batch) in batches() {
+        let schema = batch.schema();
+        group.throughput(Throughput::Elements(
+            (batch.num_rows() * batch.num_columns()) as u64,
+        ));
+        group.bench_function(name, |b| {
+            b.iter(|| convert_batches(&schema, vec![batch.clone()]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// A low-cardinality dictionary column, converted with and without the dictionary cache.
+fn bench_dictionary_cache(c: &mut Criterion) {
+    let batch = dictionary_batch();
+    let schema = batch.schema();
+    let mut group = c.benchmark_group("dictionary_cache");
+    group.throughput(Throughput::Elements(batch.num_rows() as u64));
+    for enabled in [false, true] {
+        let options = ConvertOptions::new().with_dictionary_cache(enabled);
+        let name = if enabled { "cached" } else { "uncached" };
+        group.bench_function(name, |b| {
+            b.iter(|| convert_batches_with_options(&schema, vec![batch.clone()], &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_cell_to_string(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cell_to_string");
+    for (name, batch) in batches() {
+        let column = batch.column(0);
+        group.throughput(Throughput::Elements(batch.num_rows() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for row in 0..column.len() {
+                    cell_to_string(column, row).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_convert_batches,
+    bench_dictionary_cache,
+    bench_cell_to_string
+);
+criterion_main!(benches);
@@ -0,0 +1,118 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::FlightSqlLogicTestError;
+
+type TokenFuture =
+    Pin<Box<dyn Future<Output = Result<String, Box<dyn Error + Send + Sync>>> + Send>>;
+
+/// Supplies bearer tokens that expire, e.g. from an OAuth or OIDC flow.
+///
+/// The provider is invoked before each RPC. With [`TokenProvider::with_cache_for`] a token
+/// is reused until it is about to expire.
+#[derive(Clone)]
+pub struct TokenProvider {
+    provider: Arc<dyn Fn() -> TokenFuture + Send + Sync>,
+    cache_for: Duration,
+    cached: Arc<Mutex<Option<(String, Instant)>>>,
+}
+
+impl TokenProvider {
+    pub fn new<F, Fut, E>(provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, E>> + Send + 'static,
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let provider = move || -> TokenFuture {
+            let token = provider();
+            Box::pin(async move { token.await.map_err(Into::into) })
+        };
+        Self {
+            provider: Arc::new(provider),
+            cache_for: Duration::ZERO,
+            cached: Arc::default(),
+        }
+    }
+
+    /// Reuses each token for `duration` before asking the provider for a new one.
+    ///
+    /// Choose a duration somewhat shorter than the token lifetime, so tokens are refreshed
+    /// before the server rejects them.
+    pub fn with_cache_for(mut self, duration: Duration) -> Self {
+        self.cache_for = duration;
+        self
+    }
+
+    /// Returns the cached token, refreshing it if it is stale.
+    ///
+    /// Provider errors are returned as [`FlightSqlLogicTestError::Auth`].
+    pub(crate) async fn token(&self) -> Result<String, FlightSqlLogicTestError> {
+        if let Some((token, fetched_at)) = self.cached.lock().unwrap().as_ref()
+            && fetched_at.elapsed() < self.cache_for
+        {
+            return Ok(token.clone());
+        }
+
+        let token = (self.provider)()
+            .await
+            .map_err(|e| FlightSqlLogicTestError::Auth(e.to_string()))?;
+        *self.cached.lock().unwrap() = Some((token.clone(), Instant::now()));
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::TokenProvider;
+    use crate::error::FlightSqlLogicTestError;
+
+    fn counting_provider() -> (TokenProvider, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = TokenProvider::new({
+            let calls = calls.clone();
+            move || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move { Ok::<_, FlightSqlLogicTestError>(format!("token-{call}")) }
+            }
+        });
+        (provider, calls)
+    }
+
+    #[tokio::test]
+    async fn refreshes_token_on_every_call_without_cache() {
+        let (provider, calls) = counting_provider();
+        assert_eq!(provider.token().await.unwrap(), "token-0");
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn reuses_cached_token_until_stale() {
+        let (provider, calls) = counting_provider();
+        let provider = provider.with_cache_for(Duration::from_millis(100));
+        assert_eq!(provider.token().await.unwrap(), "token-0");
+        assert_eq!(provider.token().await.unwrap(), "token-0");
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(provider.token().await.unwrap(), "token-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn provider_errors_are_auth_errors() {
+        let provider = TokenProvider::new(|| async { Err::<String, _>("identity provider down") });
+        let error = provider.token().await.unwrap_err();
+        assert!(
+            matches!(&error, FlightSqlLogicTestError::Auth(message) if message == "identity provider down"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! A blocking wrapper around [`FlightSqlDB`] for non-async test harnesses.
+//!
+//! [`BlockingFlightSqlDB`] owns a current-thread Tokio runtime and drives every call to
+//! completion on it. Like other blocking clients, it must not be created, used or dropped from
+//! within an async runtime: blocking on the owned runtime from inside another one panics. Use
+//! [`FlightSqlDB`] directly in async code.
+
+use arrow::{array::RecordBatch, datatypes::Schema};
+use sqllogictest::{AsyncDB, DB, DBOutput};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    builder::FlightSqlDBBuilder, column::ArrowColumnType, error::FlightSqlLogicTestError,
+    runner::FlightSqlDB,
+};
+
+/// A [`FlightSqlDB`] exposing synchronous methods, see the [module docs](self).
+pub struct BlockingFlightSqlDB {
+    db: FlightSqlDB,
+    runtime: Runtime,
+}
+
+impl BlockingFlightSqlDB {
+    /// Connects to `endpoint` and runs the handshake, like [`FlightSqlDB::new_from_endpoint`].
+    pub fn new_from_endpoint(
+        engine_name: impl Into<String>,
+        endpoint: impl Into<String>,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> Result<Self, FlightSqlLogicTestError> {
+        Self::connect(
+            FlightSqlDBBuilder::new(engine_name, endpoint)
+                .with_credentials(username.as_ref(), password.as_ref()),
+        )
+    }
+
+    /// Builds a [`FlightSqlDB`] from `builder` on a new runtime.
+    ///
+    /// The connection is created on the owned runtime, as its background tasks must keep
+    /// running on the runtime driving the requests.
+    pub fn connect(builder: FlightSqlDBBuilder) -> Result<Self, FlightSqlLogicTestError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create runtime: {e}"))?;
+        let db = runtime.block_on(builder.build())?;
+        Ok(Self { db, runtime })
+    }
+
+    /// Executes `query` and collects all result batches, see [`FlightSqlDB::execute`].
+    pub fn execute(
+        &mut self,
+        query: impl Into<String>,
+    ) -> Result<(Schema, Vec<RecordBatch>), FlightSqlLogicTestError> {
+        self.runtime.block_on(self.db.execute(query))
+    }
+}
+
+impl DB for BlockingFlightSqlDB {
+    type Error = FlightSqlLogicTestError;
+    type ColumnType = ArrowColumnType;
+
+    fn run(&mut self, sql: &str) -> Result<DBOutput<Self::ColumnType>, Self::Error> {
+        self.runtime.block_on(AsyncDB::run(&mut self.db, sql))
+    }
+
+    fn engine_name(&self) -> &str {
+        AsyncDB::engine_name(&self.db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use sqllogictest::{DB, DBOutput};
+
+    use super::BlockingFlightSqlDB;
+    use crate::column::ArrowColumnType;
+    use crate::test_util::TestFlightSqlService;
+
+    #[test]
+    fn runs_queries_without_an_async_runtime() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1, 2]))])
+                .unwrap();
+        let service =
+            TestFlightSqlService::new().with_result("select a from t", schema, vec![vec![batch]]);
+        // The server runs on its own runtime, the db on the one it owns.
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let endpoint = server_runtime.block_on(service.serve());
+
+        let mut db =
+            BlockingFlightSqlDB::new_from_endpoint("demo-db", endpoint, "admin", "password")
+                .unwrap();
+
+        let (_, batches) = db.execute("select a from t").unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+
+        let DBOutput::Rows { types, rows } = db.run("select a from t").unwrap() else {
+            panic!("query should return rows");
+        };
+        assert_eq!(types, vec![ArrowColumnType::Integer]);
+        assert_eq!(rows, vec![vec!["1".to_string()], vec!["2".to_string()]]);
+        assert_eq!(db.engine_name(), "demo-db");
+    }
+}
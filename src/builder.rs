@@ -0,0 +1,746 @@
+use std::error::Error;
+use std::time::Duration;
+
+use arrow_flight::sql::client::FlightSqlServiceClient;
+use hyper::rt;
+use tonic::codegen::Service;
+use tonic::service::Interceptor;
+#[cfg(feature = "tls")]
+use tonic::transport::ClientTlsConfig;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+use crate::{auth::TokenProvider, error::FlightSqlLogicTestError, runner::FlightSqlDB};
+
+/// Environment variable holding the endpoint to connect to. Required by
+/// [`FlightSqlDBBuilder::from_env`].
+pub const ENV_ENDPOINT: &str = "FLIGHTSQL_ENDPOINT";
+/// Environment variable holding a bearer token, see [`FlightSqlDBBuilder::with_token`].
+pub const ENV_TOKEN: &str = "FLIGHTSQL_TOKEN";
+/// Environment variable holding the handshake username. Must be set together with
+/// [`ENV_PASSWORD`].
+pub const ENV_USERNAME: &str = "FLIGHTSQL_USERNAME";
+/// Environment variable holding the handshake password. Must be set together with
+/// [`ENV_USERNAME`].
+pub const ENV_PASSWORD: &str = "FLIGHTSQL_PASSWORD";
+/// Environment variable enabling TLS when set to `true` or `1`. Requires the `tls` feature.
+pub const ENV_TLS: &str = "FLIGHTSQL_TLS";
+/// Environment variable holding the request timeout in seconds, e.g. `30` or `2.5`.
+pub const ENV_TIMEOUT: &str = "FLIGHTSQL_TIMEOUT";
+
+/// Port connected to when an endpoint has none and a plaintext scheme, the port commonly used
+/// by gRPC servers.
+pub const DEFAULT_PORT: u16 = 50051;
+/// Port connected to when an endpoint has none and a TLS scheme.
+pub const DEFAULT_TLS_PORT: u16 = 443;
+
+/// Largest HTTP/2 flow-control window, `2^31 - 1` bytes as defined by RFC 9113.
+pub const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+/// Builds a [`FlightSqlDB`] connected to an endpoint with custom connection options.
+pub struct FlightSqlDBBuilder {
+    engine_name: String,
+    endpoint: String,
+    credentials: Option<(String, String)>,
+    token: Option<String>,
+    token_provider: Option<TokenProvider>,
+    interceptor: Option<Box<dyn Interceptor + Send>>,
+    headers: Vec<(String, String)>,
+    authority: Option<String>,
+    timeout: Option<Duration>,
+    stream_window_size: Option<u32>,
+    connection_window_size: Option<u32>,
+    concurrency_limit: Option<usize>,
+    #[cfg(feature = "tls")]
+    tls: Option<ClientTlsConfig>,
+}
+
+impl FlightSqlDBBuilder {
+    /// Creates a builder connecting to `endpoint`, e.g. `http://host:50051`, `host:50051` or
+    /// a Flight location like `grpc+tls://host`.
+    ///
+    /// A missing scheme defaults to `http`, or `https` with TLS enabled, and a missing port to
+    /// [`DEFAULT_TLS_PORT`] for TLS schemes and [`DEFAULT_PORT`] otherwise. Invalid endpoints
+    /// make connecting fail with [`FlightSqlLogicTestError::Other`].
+    pub fn new(engine_name: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self {
+            engine_name: engine_name.into(),
+            endpoint: endpoint.into(),
+            credentials: None,
+            token: None,
+            token_provider: None,
+            interceptor: None,
+            headers: Vec::new(),
+            authority: None,
+            timeout: None,
+            stream_window_size: None,
+            connection_window_size: None,
+            concurrency_limit: None,
+            #[cfg(feature = "tls")]
+            tls: None,
+        }
+    }
+
+    /// Creates a builder from the `FLIGHTSQL_*` environment variables.
+    ///
+    /// [`ENV_ENDPOINT`] is required; [`ENV_TOKEN`], [`ENV_USERNAME`]/[`ENV_PASSWORD`],
+    /// [`ENV_TLS`] and [`ENV_TIMEOUT`] are optional. Options set on the returned builder
+    /// take precedence over the values read from the environment.
+    pub fn from_env(engine_name: impl Into<String>) -> Result<Self, FlightSqlLogicTestError> {
+        Self::from_vars(engine_name, |name| std::env::var(name).ok())
+    }
+
+    fn from_vars(
+        engine_name: impl Into<String>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, FlightSqlLogicTestError> {
+        let endpoint = var(ENV_ENDPOINT)
+            .ok_or_else(|| format!("Missing required environment variable {ENV_ENDPOINT}"))?;
+        let mut builder = Self::new(engine_name, endpoint);
+
+        match (var(ENV_USERNAME), var(ENV_PASSWORD)) {
+            (Some(username), Some(password)) => {
+                builder = builder.with_credentials(username, password);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(format!(
+                    "Environment variables {ENV_USERNAME} and {ENV_PASSWORD} must be set together"
+                )
+                .into());
+            }
+        }
+        if let Some(token) = var(ENV_TOKEN) {
+            builder = builder.with_token(token);
+        }
+        if let Some(tls) = var(ENV_TLS) {
+            let enabled = match tls.to_ascii_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => {
+                    return Err(
+                        format!("Invalid value {tls:?} for {ENV_TLS}, expected a boolean").into(),
+                    );
+                }
+            };
+            if enabled {
+                #[cfg(feature = "tls")]
+                {
+                    builder = builder.with_tls();
+                }
+                #[cfg(not(feature = "tls"))]
+                return Err(format!("{ENV_TLS} requires the `tls` feature").into());
+            }
+        }
+        if let Some(timeout) = var(ENV_TIMEOUT) {
+            let timeout = timeout
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or_else(|| {
+                    format!("Invalid value {timeout:?} for {ENV_TIMEOUT}, expected seconds")
+                })?;
+            builder = builder.with_timeout(timeout);
+        }
+        Ok(builder)
+    }
+
+    /// Sets the username and password used for the handshake.
+    ///
+    /// No handshake is performed when credentials are not set.
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Sets a bearer token sent with every request.
+    ///
+    /// The token takes precedence over one returned by the handshake.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Authorizes every request with a bearer token from `token_provider`, refreshing it as
+    /// configured there. Takes precedence over [`FlightSqlDBBuilder::with_token`] and the
+    /// token returned by the handshake, which is authorized by the credentials instead.
+    pub fn with_token_provider(mut self, token_provider: TokenProvider) -> Self {
+        self.token_provider = Some(token_provider);
+        self
+    }
+
+    /// Runs `interceptor` on every request, including the handshake, e.g. to add request ids
+    /// or metrics.
+    ///
+    /// Requests are sent over an [`InterceptedService`] wrapping the connection channel, so
+    /// TLS, timeouts and the other connection options still apply. The interceptor sees the
+    /// metadata of each request with the headers and bearer token already set, and may
+    /// change any of it, binary metadata included. Returning an error status fails the request
+    /// with that status without sending it. The interceptor must be `Send`; it is shared by
+    /// clones of the built db, which call it one at a time.
+    ///
+    /// [`InterceptedService`]: tonic::service::interceptor::InterceptedService
+    pub fn with_interceptor(mut self, interceptor: Box<dyn Interceptor + Send>) -> Self {
+        self.interceptor = Some(interceptor);
+        self
+    }
+
+    /// Adds a header sent with every request.
+    ///
+    /// Invalid header names or values make requests fail.
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the timeout applied to each request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the HTTP/2 flow-control window of each stream, in bytes.
+    ///
+    /// Defaults to hyper's 2 MiB. An explicit size also turns off adaptive (BDP-based) window
+    /// sizing, so only set it when a fixed window is known to suit the server and network.
+    /// Must be between 1 and [`MAX_WINDOW_SIZE`], which is checked when connecting.
+    pub fn with_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.stream_window_size = Some(size);
+        self
+    }
+
+    /// Sets the HTTP/2 flow-control window shared by all streams of the connection, in bytes.
+    ///
+    /// Defaults to hyper's 5 MiB. An explicit size also turns off adaptive (BDP-based) window
+    /// sizing, like [`FlightSqlDBBuilder::with_initial_stream_window_size`]. Must be between 1
+    /// and [`MAX_WINDOW_SIZE`], which is checked when connecting.
+    pub fn with_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.connection_window_size = Some(size);
+        self
+    }
+
+    /// Limits the number of requests in flight on the channel with a tower concurrency limit,
+    /// further ones wait for a slot. Unlimited by default; must not be zero, which is checked
+    /// when connecting.
+    ///
+    /// This is enforced by the client alone. It is not the HTTP/2 `max_concurrent_streams`
+    /// setting, which the server advertises and which applies independently.
+    pub fn with_concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Enables TLS using the enabled root certificates.
+    #[cfg(feature = "tls")]
+    pub fn with_tls(self) -> Self {
+        self.with_tls_config(ClientTlsConfig::new().with_enabled_roots())
+    }
+
+    /// Enables TLS with a custom configuration, e.g. to set the SNI domain name or a CA.
+    #[cfg(feature = "tls")]
+    pub fn with_tls_config(mut self, config: ClientTlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Overrides the HTTP/2 `:authority` pseudo-header sent with every request.
+    ///
+    /// This is useful when routing through host-based proxies, where the authority a request
+    /// is addressed to differs from the address dialed. It does not change the TLS SNI.
+    pub fn with_authority(mut self, authority: impl Into<String>) -> Self {
+        self.authority = Some(authority.into());
+        self
+    }
+
+    /// Connects a channel to the endpoint with the authority, timeout, flow-control and TLS
+    /// options.
+    ///
+    /// Credentials, tokens and headers are not applied, as they are per request. The channel
+    /// is cheap to clone, so it can be shared with other gRPC clients and passed to
+    /// [`FlightSqlDBBuilder::build_with_channel`].
+    pub async fn connect_channel(&self) -> Result<Channel, FlightSqlLogicTestError> {
+        Ok(self.channel_endpoint()?.connect().await?)
+    }
+
+    /// Like [`FlightSqlDBBuilder::connect_channel`], but dials connections with `connector`
+    /// instead of TCP.
+    ///
+    /// `connector` is a [`Service`] called with the endpoint URI whenever the channel needs a
+    /// new connection, including after a transport failure. It returns an I/O stream
+    /// implementing hyper's [`rt::Read`] and [`rt::Write`], e.g. a tokio stream wrapped in
+    /// `hyper_util::rt::TokioIo`; its errors must convert into a boxed `std::error::Error`.
+    /// This allows custom transports, and tests injecting connections that fail on command.
+    pub async fn connect_channel_with_connector<C>(
+        &self,
+        connector: C,
+    ) -> Result<Channel, FlightSqlLogicTestError>
+    where
+        C: Service<Uri> + Send + 'static,
+        C::Response: rt::Read + rt::Write + Send + Unpin,
+        C::Future: Send,
+        Box<dyn Error + Send + Sync>: From<C::Error> + Send,
+    {
+        Ok(self
+            .channel_endpoint()?
+            .connect_with_connector(connector)
+            .await?)
+    }
+
+    /// Returns the endpoint with the authority, timeout, flow-control and TLS options applied.
+    fn channel_endpoint(&self) -> Result<Endpoint, FlightSqlLogicTestError> {
+        #[cfg(feature = "tls")]
+        let tls = self.tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        let tls = false;
+        let mut endpoint = Endpoint::from_shared(normalize_endpoint(&self.endpoint, tls)?)?;
+        if let Some(authority) = &self.authority {
+            let scheme = endpoint.uri().scheme_str().unwrap_or("http");
+            let origin = Uri::builder()
+                .scheme(scheme)
+                .authority(authority.as_str())
+                .path_and_query("/")
+                .build()
+                .map_err(|e| format!("Invalid authority {authority:?}: {e}"))?;
+            endpoint = endpoint.origin(origin);
+        }
+        if let Some(timeout) = self.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        for (name, size) in [
+            ("stream", self.stream_window_size),
+            ("connection", self.connection_window_size),
+        ] {
+            match size {
+                Some(size) if !(1..=MAX_WINDOW_SIZE).contains(&size) => {
+                    return Err(format!(
+                        "Invalid initial {name} window size {size}: must be between 1 and \
+                         {MAX_WINDOW_SIZE}"
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
+        endpoint = endpoint
+            .initial_stream_window_size(self.stream_window_size)
+            .initial_connection_window_size(self.connection_window_size);
+        if let Some(limit) = self.concurrency_limit {
+            if limit == 0 {
+                return Err("Invalid concurrency limit 0: must be at least 1"
+                    .to_string()
+                    .into());
+            }
+            endpoint = endpoint.concurrency_limit(limit);
+        }
+        #[cfg(feature = "tls")]
+        if let Some(tls) = self.tls.clone() {
+            endpoint = endpoint.tls_config(tls)?;
+        }
+        Ok(endpoint)
+    }
+
+    /// Connects to the endpoint and, if credentials are set, runs the handshake.
+    pub async fn build(self) -> Result<FlightSqlDB, FlightSqlLogicTestError> {
+        let channel = self.connect_channel().await?;
+        self.build_with_channel(channel).await
+    }
+
+    /// Like [`FlightSqlDBBuilder::build`], but connects with `connector`, see
+    /// [`FlightSqlDBBuilder::connect_channel_with_connector`].
+    pub async fn build_with_connector<C>(
+        self,
+        connector: C,
+    ) -> Result<FlightSqlDB, FlightSqlLogicTestError>
+    where
+        C: Service<Uri> + Send + 'static,
+        C::Response: rt::Read + rt::Write + Send + Unpin,
+        C::Future: Send,
+        Box<dyn Error + Send + Sync>: From<C::Error> + Send,
+    {
+        let channel = self.connect_channel_with_connector(connector).await?;
+        self.build_with_channel(channel).await
+    }
+
+    /// Like [`FlightSqlDBBuilder::build`], but uses `channel` instead of connecting, e.g. one
+    /// from [`FlightSqlDBBuilder::connect_channel`] that is shared with other clients.
+    pub async fn build_with_channel(
+        self,
+        channel: Channel,
+    ) -> Result<FlightSqlDB, FlightSqlLogicTestError> {
+        let mut client = FlightSqlServiceClient::new(channel.clone());
+        for (key, value) in &self.headers {
+            client.set_header(key, value);
+        }
+        let mut db = FlightSqlDB::from_client(self.engine_name, client).with_headers(self.headers);
+        if let Some(interceptor) = self.interceptor {
+            db = db.with_interceptor(channel, interceptor);
+        }
+        if let Some((username, password)) = self.credentials {
+            db.handshake(&username, &password).await?;
+        }
+        if let Some(token) = self.token {
+            db = db.with_token(token);
+        }
+        if let Some(token_provider) = self.token_provider {
+            db = db.with_token_provider(token_provider);
+        }
+        Ok(db)
+    }
+}
+
+/// Normalizes `endpoint` into a URI accepted by [`Endpoint::from_shared`].
+///
+/// Endpoints without a scheme, like `host:50051`, use `https` if `tls` is set and `http`
+/// otherwise. The `grpc` and `grpc+tcp` schemes of Arrow Flight locations map to `http`, and
+/// `grpc+tls` to `https`; other schemes are rejected. Endpoints without a port use
+/// [`DEFAULT_TLS_PORT`] for TLS schemes and [`DEFAULT_PORT`] otherwise.
+fn normalize_endpoint(endpoint: &str, tls: bool) -> Result<String, FlightSqlLogicTestError> {
+    let invalid = |reason: &dyn std::fmt::Display| -> FlightSqlLogicTestError {
+        format!("Invalid endpoint {endpoint:?}: {reason}").into()
+    };
+    let (scheme, rest) = match endpoint.split_once("://") {
+        Some((scheme, rest)) => (scheme.to_ascii_lowercase(), rest),
+        None if tls => ("https".to_string(), endpoint),
+        None => ("http".to_string(), endpoint),
+    };
+    let (scheme, default_port) = match scheme.as_str() {
+        "http" | "grpc" | "grpc+tcp" => ("http", DEFAULT_PORT),
+        "https" | "grpc+tls" => ("https", DEFAULT_TLS_PORT),
+        other => {
+            return Err(invalid(&format_args!(
+                "unsupported scheme {other:?}, expected http, https, grpc, grpc+tcp or grpc+tls"
+            )));
+        }
+    };
+
+    let uri = format!("{scheme}://{rest}")
+        .parse::<Uri>()
+        .map_err(|e| invalid(&e))?;
+    let authority = match uri.authority() {
+        Some(authority) if !authority.host().is_empty() => authority,
+        _ => return Err(invalid(&"missing host")),
+    };
+    if authority.port().is_some() {
+        return Ok(uri.to_string());
+    }
+    Uri::builder()
+        .scheme(scheme)
+        .authority(format!("{authority}:{default_port}"))
+        .path_and_query(uri.path_and_query().map_or("/", |path| path.as_str()))
+        .build()
+        .map(|uri| uri.to_string())
+        .map_err(|e| invalid(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow_flight::flight_service_server::FlightServiceServer;
+    use arrow_flight::sql::client::FlightSqlServiceClient;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+    use tower::util::MapRequestLayer;
+
+    use super::{
+        ENV_ENDPOINT, ENV_PASSWORD, ENV_TIMEOUT, ENV_TOKEN, ENV_USERNAME, FlightSqlDBBuilder,
+        MAX_WINDOW_SIZE, normalize_endpoint,
+    };
+    use crate::error::FlightSqlLogicTestError;
+    use crate::test_util::{FlakyConnector, TestFlightSqlService};
+
+    /// Serves a test service recording the `:authority` of every request.
+    async fn spawn_authority_recording_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("test server should bind");
+        let addr = listener.local_addr().expect("test server should have addr");
+        let authorities = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = authorities.clone();
+        let record_authority = MapRequestLayer::new(move |request: http::Request<_>| {
+            if let Some(authority) = request.uri().authority() {
+                recorded.lock().unwrap().push(authority.to_string());
+            }
+            request
+        });
+        tokio::spawn(async move {
+            Server::builder()
+                .layer(record_authority)
+                .add_service(FlightServiceServer::new(TestFlightSqlService::new()))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .expect("test server should serve");
+        });
+
+        (format!("http://{addr}"), authorities)
+    }
+
+    #[tokio::test]
+    async fn with_authority_overrides_authority_header() {
+        let (endpoint, authorities) = spawn_authority_recording_server().await;
+
+        FlightSqlDBBuilder::new("demo-db", endpoint)
+            .with_credentials("admin", "password")
+            .with_authority("flightsql.internal:443")
+            .build()
+            .await
+            .expect("db should be created");
+
+        assert_eq!(*authorities.lock().unwrap(), vec!["flightsql.internal:443"]);
+    }
+
+    #[tokio::test]
+    async fn authority_defaults_to_endpoint() {
+        let (endpoint, authorities) = spawn_authority_recording_server().await;
+
+        FlightSqlDBBuilder::new("demo-db", endpoint.clone())
+            .with_credentials("admin", "password")
+            .build()
+            .await
+            .expect("db should be created");
+
+        assert_eq!(
+            *authorities.lock().unwrap(),
+            vec![endpoint.trim_start_matches("http://")]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_authority_is_rejected() {
+        let result = FlightSqlDBBuilder::new("demo-db", "http://127.0.0.1:1")
+            .with_authority("not a host")
+            .build()
+            .await;
+
+        let error = result.expect_err("build should fail");
+        assert!(error.to_string().contains("Invalid authority"));
+    }
+
+    #[test]
+    fn endpoints_are_normalized() {
+        let cases = [
+            ("host:50051", false, "http://host:50051/"),
+            ("host:50051", true, "https://host:50051/"),
+            ("grpc://host", false, "http://host:50051/"),
+            ("grpc+tcp://host:8815", false, "http://host:8815/"),
+            ("grpc+tls://host", false, "https://host:443/"),
+            ("https://host", false, "https://host:443/"),
+            ("http://host", false, "http://host:50051/"),
+            ("HTTP://[::1]", false, "http://[::1]:50051/"),
+            ("http://127.0.0.1:1", false, "http://127.0.0.1:1/"),
+        ];
+        for (endpoint, tls, expected) in cases {
+            assert_eq!(
+                normalize_endpoint(endpoint, tls).unwrap(),
+                expected,
+                "{endpoint}"
+            );
+        }
+    }
+
+    #[test]
+    fn unparseable_endpoints_are_rejected() {
+        for endpoint in ["ftp://host", "http://", "host name:1", "http://host:port"] {
+            let error = normalize_endpoint(endpoint, false).expect_err(endpoint);
+            assert!(
+                matches!(&error, FlightSqlLogicTestError::Other(message)
+                    if message.starts_with(&format!("Invalid endpoint {endpoint:?}"))),
+                "{error}"
+            );
+        }
+    }
+
+    fn builder_from(vars: &[(&str, &str)]) -> Result<FlightSqlDBBuilder, String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        FlightSqlDBBuilder::from_vars("demo-db", |name| vars.get(name).cloned())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn from_env_reads_connection_settings() {
+        let builder = builder_from(&[
+            (ENV_ENDPOINT, "http://localhost:50050"),
+            (ENV_USERNAME, "admin"),
+            (ENV_PASSWORD, "password"),
+            (ENV_TOKEN, "secret"),
+            (ENV_TIMEOUT, "2.5"),
+        ])
+        .unwrap();
+
+        assert_eq!(builder.endpoint, "http://localhost:50050");
+        assert_eq!(
+            builder.credentials,
+            Some(("admin".to_string(), "password".to_string()))
+        );
+        assert_eq!(builder.token.as_deref(), Some("secret"));
+        assert_eq!(builder.timeout, Some(Duration::from_millis(2500)));
+    }
+
+    #[test]
+    fn from_env_requires_endpoint() {
+        let error = builder_from(&[]).err().unwrap();
+        assert!(error.contains(ENV_ENDPOINT));
+    }
+
+    #[test]
+    fn from_env_rejects_invalid_values() {
+        let error = builder_from(&[(ENV_ENDPOINT, "http://localhost"), (ENV_USERNAME, "admin")])
+            .err()
+            .unwrap();
+        assert!(error.contains(ENV_PASSWORD));
+
+        let error = builder_from(&[(ENV_ENDPOINT, "http://localhost"), (ENV_TIMEOUT, "soon")])
+            .err()
+            .unwrap();
+        assert!(error.contains(ENV_TIMEOUT));
+    }
+
+    #[test]
+    fn explicit_options_override_env() {
+        let builder = builder_from(&[(ENV_ENDPOINT, "http://localhost"), (ENV_TOKEN, "env")])
+            .unwrap()
+            .with_token("explicit");
+
+        assert_eq!(builder.token.as_deref(), Some("explicit"));
+    }
+
+    #[tokio::test]
+    async fn channel_is_shared_with_other_clients() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        let service =
+            TestFlightSqlService::new().with_result("select a from t", schema, vec![vec![batch]]);
+        let builder = FlightSqlDBBuilder::new("demo-db", service.serve().await);
+
+        let channel = builder.connect_channel().await.unwrap();
+        let mut client = FlightSqlServiceClient::new(channel.clone());
+        let mut db = builder.build_with_channel(channel).await.unwrap();
+
+        client
+            .execute("select a from t".to_string(), None)
+            .await
+            .unwrap();
+        let (_, batches) = db.execute("select a from t").await.unwrap();
+        assert_eq!(batches.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn flow_control_options_are_accepted() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        let service =
+            TestFlightSqlService::new().with_result("select a from t", schema, vec![vec![batch]]);
+        let mut db = FlightSqlDBBuilder::new("demo-db", service.serve().await)
+            .with_initial_stream_window_size(4 * 1024 * 1024)
+            .with_initial_connection_window_size(MAX_WINDOW_SIZE)
+            .with_concurrency_limit(1)
+            .build()
+            .await
+            .unwrap();
+        let (_, batches) = db.execute("select a from t").await.unwrap();
+        assert_eq!(batches.len(), 1);
+    }
+
+    #[test]
+    fn window_sizes_are_checked_at_their_bounds() {
+        let builder = || FlightSqlDBBuilder::new("demo-db", "http://127.0.0.1:1");
+        for size in [1, MAX_WINDOW_SIZE] {
+            let stream = builder().with_initial_stream_window_size(size);
+            let connection = builder().with_initial_connection_window_size(size);
+            assert!(stream.channel_endpoint().is_ok(), "stream window {size}");
+            assert!(
+                connection.channel_endpoint().is_ok(),
+                "connection window {size}"
+            );
+        }
+        for size in [0, MAX_WINDOW_SIZE + 1] {
+            let expected = |name: &str| {
+                format!(
+                    "Invalid initial {name} window size {size}: must be between 1 and \
+                     {MAX_WINDOW_SIZE}"
+                )
+            };
+            let stream = builder().with_initial_stream_window_size(size);
+            let error = stream.channel_endpoint().unwrap_err();
+            assert!(
+                matches!(&error, FlightSqlLogicTestError::Other(message) if *message == expected("stream")),
+                "{error}"
+            );
+            let connection = builder().with_initial_connection_window_size(size);
+            let error = connection.channel_endpoint().unwrap_err();
+            assert!(
+                matches!(&error, FlightSqlLogicTestError::Other(message) if *message == expected("connection")),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn concurrency_limit_is_checked_at_its_bound() {
+        let builder = || FlightSqlDBBuilder::new("demo-db", "http://127.0.0.1:1");
+        assert!(
+            builder()
+                .with_concurrency_limit(1)
+                .channel_endpoint()
+                .is_ok()
+        );
+        let error = builder()
+            .with_concurrency_limit(0)
+            .channel_endpoint()
+            .unwrap_err();
+        assert!(
+            matches!(&error, FlightSqlLogicTestError::Other(message)
+                if message == "Invalid concurrency limit 0: must be at least 1"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_connector_reconnects_after_transport_failure() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        let service =
+            TestFlightSqlService::new().with_result("select a from t", schema, vec![vec![batch]]);
+        let connector = FlakyConnector::default();
+        let mut db = FlightSqlDBBuilder::new("demo-db", service.serve().await)
+            .with_timeout(Duration::from_secs(10))
+            .build_with_connector(connector.clone())
+            .await
+            .unwrap();
+        db.execute("select a from t").await.unwrap();
+        assert_eq!(connector.connects(), 1);
+
+        connector.fail();
+        assert!(db.execute("select a from t").await.is_err());
+
+        connector.recover();
+        let (_, batches) = db.execute("select a from t").await.unwrap();
+        assert_eq!(batches.len(), 1);
+        assert!(connector.connects() > 1);
+    }
+
+    #[tokio::test]
+    async fn failing_connector_fails_build() {
+        let connector = FlakyConnector::default();
+        connector.fail();
+        let result = FlightSqlDBBuilder::new("demo-db", "http://127.0.0.1:1")
+            .build_with_connector(connector)
+            .await;
+        assert!(matches!(result, Err(FlightSqlLogicTestError::Tonic(_))));
+    }
+}
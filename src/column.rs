@@ -1,24 +1,37 @@
+use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use arrow::array::*;
-use arrow::datatypes::Fields;
+use arrow::compute::cast;
+use arrow::datatypes::{Field, Fields};
 use arrow::{
     datatypes::{DataType, Decimal128Type, Decimal256Type, DecimalType, Schema, i256},
-    util::display::{ArrayFormatter, FormatOptions},
+    error::ArrowError,
+    util::display::{
+        ArrayFormatter, ArrayFormatterFactory, DisplayIndex, FormatError, FormatOptions,
+        FormatResult,
+    },
 };
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Zero};
 use half::f16;
 use sqllogictest::ColumnType;
 
 use crate::error::FlightSqlLogicTestError;
+use crate::output::line_diff;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ArrowColumnType {
     Boolean,
+    /// Dates (`Date32`/`Date64`), and times of day unless
+    /// [`ConvertOptions::with_time_column_type`] is enabled.
     DateTime,
     Integer,
     Float,
     Text,
+    /// Times of day (`Time32`/`Time64`), written as `H`, see
+    /// [`ConvertOptions::with_time_column_type`].
+    Time,
     Timestamp,
     Another,
 }
@@ -28,6 +41,7 @@ impl ColumnType for ArrowColumnType {
         match value {
             'B' => Some(Self::Boolean),
             'D' => Some(Self::DateTime),
+            'H' => Some(Self::Time),
             'I' => Some(Self::Integer),
             'P' => Some(Self::Timestamp),
             'R' => Some(Self::Float),
@@ -40,6 +54,7 @@ impl ColumnType for ArrowColumnType {
         match self {
             Self::Boolean => 'B',
             Self::DateTime => 'D',
+            Self::Time => 'H',
             Self::Integer => 'I',
             Self::Timestamp => 'P',
             Self::Float => 'R',
@@ -51,80 +66,558 @@ impl ColumnType for ArrowColumnType {
 
 /// Converts columns to a result as expected by sqllogicteset.
 pub fn convert_schema_to_types(columns: &Fields) -> Vec<ArrowColumnType> {
+    convert_schema_to_types_with_options(columns, &ConvertOptions::default())
+}
+
+/// Converts columns like [`convert_schema_to_types`], using the given `options`.
+pub fn convert_schema_to_types_with_options(
+    columns: &Fields,
+    options: &ConvertOptions,
+) -> Vec<ArrowColumnType> {
     columns
         .iter()
-        .map(|f| f.data_type())
-        .map(|data_type| match data_type {
-            DataType::Boolean => ArrowColumnType::Boolean,
-            DataType::Int8
-            | DataType::Int16
-            | DataType::Int32
-            | DataType::Int64
-            | DataType::UInt8
-            | DataType::UInt16
-            | DataType::UInt32
-            | DataType::UInt64 => ArrowColumnType::Integer,
-            DataType::Float16
-            | DataType::Float32
-            | DataType::Float64
-            | DataType::Decimal128(_, _)
-            | DataType::Decimal256(_, _) => ArrowColumnType::Float,
-            DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => ArrowColumnType::Text,
-            DataType::Date32 | DataType::Date64 | DataType::Time32(_) | DataType::Time64(_) => {
-                ArrowColumnType::DateTime
-            }
-            DataType::Timestamp(_, _) => ArrowColumnType::Timestamp,
-            DataType::Dictionary(key_type, value_type) => {
-                if key_type.is_integer() {
-                    // mapping dictionary string types to Text
-                    match value_type.as_ref() {
-                        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
-                            ArrowColumnType::Text
-                        }
-                        _ => ArrowColumnType::Another,
+        .map(|f| match column_type(f.data_type()) {
+            ArrowColumnType::Time if !options.time_column_type => ArrowColumnType::DateTime,
+            column_type => column_type,
+        })
+        .collect()
+}
+
+fn column_type(data_type: &DataType) -> ArrowColumnType {
+    match data_type {
+        // Untyped columns, like `SELECT NULL`, hold only nulls and are written as text.
+        DataType::Null => ArrowColumnType::Text,
+        DataType::Boolean => ArrowColumnType::Boolean,
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => ArrowColumnType::Integer,
+        DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => ArrowColumnType::Float,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => ArrowColumnType::Text,
+        // Intervals and durations have no dedicated type char, render them as text.
+        DataType::Interval(_) | DataType::Duration(_) => ArrowColumnType::Text,
+        DataType::Date32 | DataType::Date64 => ArrowColumnType::DateTime,
+        DataType::Time32(_) | DataType::Time64(_) => ArrowColumnType::Time,
+        DataType::Timestamp(_, _) => ArrowColumnType::Timestamp,
+        DataType::Dictionary(key_type, value_type) => {
+            if key_type.is_integer() {
+                // mapping dictionary string types to Text
+                match value_type.as_ref() {
+                    DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+                        ArrowColumnType::Text
                     }
-                } else {
-                    ArrowColumnType::Another
+                    _ => ArrowColumnType::Another,
                 }
+            } else {
+                ArrowColumnType::Another
             }
-            _ => ArrowColumnType::Another,
-        })
-        .collect()
+        }
+        _ => ArrowColumnType::Another,
+    }
+}
+
+/// Details of a result column beyond its [`ArrowColumnType`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnTypeDetail {
+    pub name: String,
+    pub column_type: ArrowColumnType,
+    pub data_type: DataType,
+    /// Precision of decimal columns.
+    pub precision: Option<u8>,
+    /// Scale of decimal columns.
+    pub scale: Option<i8>,
+    /// Width in bytes of fixed-size columns.
+    pub byte_width: Option<usize>,
+    /// Details of the element of list columns or of the fields of struct columns. Only one
+    /// level deep, the children have no children of their own.
+    pub children: Vec<ColumnTypeDetail>,
+}
+
+impl ColumnTypeDetail {
+    fn new(field: &Field) -> Self {
+        let children = match field.data_type() {
+            DataType::List(element)
+            | DataType::LargeList(element)
+            | DataType::FixedSizeList(element, _) => vec![Self::without_children(element)],
+            DataType::Struct(fields) => fields.iter().map(|f| Self::without_children(f)).collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            children,
+            ..Self::without_children(field)
+        }
+    }
+
+    fn without_children(field: &Field) -> Self {
+        let data_type = field.data_type();
+        let (precision, scale) = match data_type {
+            DataType::Decimal32(precision, scale)
+            | DataType::Decimal64(precision, scale)
+            | DataType::Decimal128(precision, scale)
+            | DataType::Decimal256(precision, scale) => (Some(*precision), Some(*scale)),
+            _ => (None, None),
+        };
+        let byte_width = match data_type {
+            DataType::FixedSizeBinary(width) => usize::try_from(*width).ok(),
+            _ => data_type.primitive_width(),
+        };
+        Self {
+            name: field.name().clone(),
+            column_type: column_type(data_type),
+            data_type: data_type.clone(),
+            precision,
+            scale,
+            byte_width,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Like [`convert_schema_to_types`], but keeps the details the type chars cannot express.
+///
+/// Times of day are always described as [`ArrowColumnType::Time`].
+pub fn column_type_details(columns: &Fields) -> Vec<ColumnTypeDetail> {
+    columns.iter().map(|f| ColumnTypeDetail::new(f)).collect()
+}
+
+/// Controls whether text values are quoted to tell them apart from the `NULL` and `(empty)`
+/// markers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextQuoting {
+    /// Text is rendered as-is, so the text `NULL` looks like a null value (default).
+    #[default]
+    Off,
+    /// Only text that would render like a marker, or like quoted text by starting and ending
+    /// with `"`, is wrapped in double quotes. The text `NULL` renders as `"NULL"` and the
+    /// text `"NULL"` as `""NULL""`, so each rendering maps back to one value.
+    Markers,
+    /// Every non-empty text value is wrapped in double quotes.
+    All,
+}
+
+/// Controls the timezone timestamps with a stored timezone are rendered in.
+///
+/// Timestamps without a timezone are always rendered as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TimestampDisplayTimezone {
+    /// Render in the timezone stored in the column type (default).
+    #[default]
+    AsStored,
+    /// Convert to UTC.
+    Utc,
+    /// Convert to the given timezone, either a name like `America/New_York` or an offset
+    /// like `+05:30`.
+    Fixed(String),
+}
+
+/// How boolean values are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoolFormat {
+    /// `true` and `false`.
+    #[default]
+    TrueFalse,
+    /// `t` and `f`, as printed by PostgreSQL.
+    Short,
+    /// `1` and `0`.
+    Numeric,
+}
+
+/// How finite floats and decimals are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberNotation {
+    /// Plain notation, e.g. `1000000000000000000000` (default).
+    #[default]
+    Plain,
+    /// Scientific notation like `1.5e21` for nonzero values whose leading digit has a power
+    /// of ten of at least `threshold` in absolute value, plain notation for the others.
+    ///
+    /// E.g. with a threshold of 6, `1000000` and `0.000001` render as `1e6` and `1e-6`, while
+    /// `999999` and `0.00001` stay plain. The mantissa is rounded to 12 decimal places, so
+    /// unlike in plain notation tiny values keep their significant digits.
+    Scientific { threshold: u32 },
+}
+
+impl BoolFormat {
+    fn render(self, value: bool) -> String {
+        match self {
+            Self::TrueFalse => bool_to_str(value),
+            Self::Short => if value { "t" } else { "f" }.to_string(),
+            Self::Numeric => if value { "1" } else { "0" }.to_string(),
+        }
+    }
+}
+
+/// Selects a result column by position or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl ColumnSelector {
+    fn matches(&self, index: usize, name: &str) -> bool {
+        match self {
+            Self::Index(i) => *i == index,
+            Self::Name(n) => n == name,
+        }
+    }
+}
+
+/// Renders result cells instead of the built-in conversion, see
+/// [`ConvertOptions::with_cell_formatter`].
+pub trait CellFormatter: Send + Sync {
+    /// Renders the cell at `row` of `col`, including null cells.
+    ///
+    /// `field` is the field of the column in the result schema, so its metadata can carry
+    /// type hints, e.g. that an `Int64` column holds durations. Call
+    /// [`cell_to_string_with_options`] to fall back to the built-in rendering.
+    fn format(
+        &self,
+        field: &Field,
+        col: &ArrayRef,
+        row: usize,
+    ) -> Result<String, FlightSqlLogicTestError>;
+}
+
+impl fmt::Debug for dyn CellFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CellFormatter").finish_non_exhaustive()
+    }
+}
+
+/// Options controlling how query results are converted for sqllogictest.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    expand_column: Option<usize>,
+    text_quoting: TextQuoting,
+    bool_format: BoolFormat,
+    timestamp_display_timezone: TimestampDisplayTimezone,
+    null_str: Option<String>,
+    empty_str: Option<String>,
+    column_empty_strs: Vec<(ColumnSelector, String)>,
+    dictionary_cache: bool,
+    number_notation: NumberNotation,
+    cell_formatter: Option<Arc<dyn CellFormatter>>,
+    max_cell_len: Option<usize>,
+    time_column_type: bool,
+}
+
+impl ConvertOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the index of the column whose multi-line values (like explain plans) are expanded
+    /// into one row per line. Defaults to the last column.
+    ///
+    /// Only one column per row is ever expanded: when several cells of a multi-column row
+    /// contain newlines the result is ambiguous, so newlines in any other column are kept
+    /// as-is. See [`expand_row`] for the layout of expanded rows.
+    pub fn with_expand_column(mut self, index: usize) -> Self {
+        self.expand_column = Some(index);
+        self
+    }
+
+    /// Sets how text values are quoted. Defaults to [`TextQuoting::Off`].
+    pub fn with_text_quoting(mut self, text_quoting: TextQuoting) -> Self {
+        self.text_quoting = text_quoting;
+        self
+    }
+
+    /// Sets how boolean values are rendered, including booleans nested in lists, list views,
+    /// structs, maps, unions or dictionaries. Defaults to [`BoolFormat::TrueFalse`].
+    ///
+    /// Other nested values keep arrow's display formatting.
+    pub fn with_bool_format(mut self, bool_format: BoolFormat) -> Self {
+        self.bool_format = bool_format;
+        self
+    }
+
+    /// Sets the timezone timestamps with a stored timezone are rendered in.
+    /// Defaults to [`TimestampDisplayTimezone::AsStored`].
+    pub fn with_timestamp_display_timezone(mut self, timezone: TimestampDisplayTimezone) -> Self {
+        self.timestamp_display_timezone = timezone;
+        self
+    }
+
+    /// Sets how finite floats and decimals are written, including numbers nested in lists,
+    /// structs or dictionaries. Defaults to [`NumberNotation::Plain`].
+    pub fn with_number_notation(mut self, notation: NumberNotation) -> Self {
+        self.number_notation = notation;
+        self
+    }
+
+    /// Sets whether [`convert_batches_with_options`] renders each value of a dictionary column
+    /// once per batch and reuses it for every row with the same key. Disabled by default.
+    ///
+    /// This speeds up large results dominated by low-cardinality dictionary columns, the
+    /// output is the same either way.
+    pub fn with_dictionary_cache(mut self, enabled: bool) -> Self {
+        self.dictionary_cache = enabled;
+        self
+    }
+
+    /// Reports `Time32`/`Time64` columns as [`ArrowColumnType::Time`], written as `H`, instead
+    /// of [`ArrowColumnType::DateTime`], written as `D` like dates. Disabled by default.
+    ///
+    /// This tells times of day apart from dates in type lines, but the type lines of existing
+    /// records over time columns must be changed from `D` to `H` when enabling it.
+    pub fn with_time_column_type(mut self, enabled: bool) -> Self {
+        self.time_column_type = enabled;
+        self
+    }
+
+    /// Renders every cell of converted batches with `formatter` instead of the built-in
+    /// conversion. Of the other options, only [`ConvertOptions::with_expand_column`] and
+    /// [`ConvertOptions::with_max_cell_len`] still apply.
+    ///
+    /// Single cells converted with [`cell_to_string_with_options`] are neither passed to the
+    /// formatter nor truncated, so it can use them as its fallback. The truncation is
+    /// applied once to the string the formatter returns.
+    pub fn with_cell_formatter(mut self, formatter: Arc<dyn CellFormatter>) -> Self {
+        self.cell_formatter = Some(formatter);
+        self
+    }
+
+    /// Truncates rendered cells longer than `max_len` bytes to their first `max_len` bytes,
+    /// rounded down to a character boundary, followed by a `...[truncated N bytes]` marker.
+    /// Unlimited by default.
+    ///
+    /// This keeps huge cells out of comparisons and logs, but changes what is compared:
+    /// expected results must contain the truncated cell with its marker, and cells only
+    /// differing past the cap compare equal.
+    ///
+    /// Only cells of converted batches are truncated, after any
+    /// [`ConvertOptions::with_cell_formatter`] has rendered them;
+    /// [`cell_to_string_with_options`] returns the full cell.
+    pub fn with_max_cell_len(mut self, max_len: usize) -> Self {
+        self.max_cell_len = Some(max_len);
+        self
+    }
+
+    /// Sets how null values are rendered, including nulls nested in lists or structs.
+    /// Defaults to [`NULL_STR`].
+    pub fn with_null_str(mut self, null_str: impl Into<String>) -> Self {
+        self.null_str = Some(null_str.into());
+        self
+    }
+
+    /// Sets how empty strings are rendered. Defaults to [`EMPTY_STR`].
+    pub fn with_empty_str(mut self, empty_str: impl Into<String>) -> Self {
+        self.empty_str = Some(empty_str.into());
+        self
+    }
+
+    /// Overrides how empty strings of the selected column are rendered, e.g. `""` to keep
+    /// them truly empty.
+    ///
+    /// Columns without an override use [`ConvertOptions::with_empty_str`]. Overrides only
+    /// apply when converting whole batches, as single cells don't know their column.
+    pub fn with_column_empty_str(
+        mut self,
+        column: ColumnSelector,
+        empty_str: impl Into<String>,
+    ) -> Self {
+        self.column_empty_strs.push((column, empty_str.into()));
+        self
+    }
+
+    fn null_str(&self) -> &str {
+        self.null_str.as_deref().unwrap_or(NULL_STR)
+    }
+
+    fn empty_str(&self) -> &str {
+        self.empty_str.as_deref().unwrap_or(EMPTY_STR)
+    }
+
+    /// Returns the empty string marker of the column at `index` named `name`.
+    fn column_empty_str(&self, index: usize, name: &str) -> &str {
+        self.column_empty_strs
+            .iter()
+            .rev()
+            .find(|(column, _)| column.matches(index, name))
+            .map_or_else(|| self.empty_str(), |(_, empty_str)| empty_str.as_str())
+    }
+}
+
+/// Settings used while rendering the cells of one column.
+struct CellContext<'a> {
+    options: &'a ConvertOptions,
+    empty_str: &'a str,
+    /// Whether floats and decimals keep their full precision instead of being rounded.
+    raw_numbers: bool,
+}
+
+impl<'a> CellContext<'a> {
+    fn new(options: &'a ConvertOptions) -> Self {
+        Self {
+            options,
+            empty_str: options.empty_str(),
+            raw_numbers: false,
+        }
+    }
 }
 
 /// Converts `batches` to a result as expected by sqllogictest.
 pub fn convert_batches(
     schema: &Schema,
     batches: Vec<RecordBatch>,
+) -> Result<Vec<Vec<String>>, FlightSqlLogicTestError> {
+    convert_batches_with_options(schema, batches, &ConvertOptions::default())
+}
+
+/// Converts `batches` to a result as expected by sqllogictest, using the given `options`.
+///
+/// Batches without columns have no cells to compare and produce no rows, whatever their row
+/// count.
+pub fn convert_batches_with_options(
+    schema: &Schema,
+    batches: Vec<RecordBatch>,
+    options: &ConvertOptions,
 ) -> Result<Vec<Vec<String>>, FlightSqlLogicTestError> {
     let mut rows = vec![];
     for batch in batches {
-        // Verify schema
-        if !schema.contains(&batch.schema()) {
-            return Err(FlightSqlLogicTestError::Other(format!(
-                "Schema mismatch. Previously had\n{:#?}\n\nGot:\n{:#?}",
-                &schema,
-                batch.schema()
-            )));
+        validate_batch_schema(schema, &batch)?;
+        if batch.num_columns() == 0 {
+            continue;
         }
 
-        // Convert a single batch to a `Vec<Vec<String>>` for comparison, flatten expanded rows, and normalize each.
-        let new_rows = (0..batch.num_rows())
-            .map(|row| {
-                batch
-                    .columns()
-                    .iter()
-                    .map(|col| cell_to_string(col, row))
-                    .collect::<Result<Vec<String>, FlightSqlLogicTestError>>()
+        let batch_schema = batch.schema();
+        let contexts = batch_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| CellContext {
+                options,
+                empty_str: options.column_empty_str(index, field.name()),
+                raw_numbers: false,
             })
-            .collect::<Result<Vec<Vec<String>>, FlightSqlLogicTestError>>()?
-            .into_iter()
-            .flat_map(expand_row);
-        rows.extend(new_rows);
+            .collect::<Vec<_>>();
+
+        let mut caches = batch
+            .columns()
+            .iter()
+            .map(|col| {
+                options
+                    .dictionary_cache
+                    .then(|| DictionaryCache::new(col))
+                    .flatten()
+            })
+            .collect::<Vec<_>>();
+
+        // Convert a single batch to a `Vec<Vec<String>>` for comparison, flatten expanded rows, and normalize each.
+        let mut new_rows = Vec::with_capacity(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            let cells = schema
+                .fields()
+                .iter()
+                .zip(batch.columns())
+                .zip(&contexts)
+                .zip(&mut caches)
+                .map(|(((field, col), context), cache)| {
+                    let cell = match (&options.cell_formatter, cache) {
+                        (Some(formatter), _) => formatter.format(field, col, row),
+                        (None, Some(cache)) => cache.render(col, row, context),
+                        (None, None) => render_cell(col, row, context),
+                    };
+                    cell.map(|cell| truncate_cell(cell, options.max_cell_len))
+                })
+                .collect::<Result<Vec<String>, FlightSqlLogicTestError>>()?;
+            new_rows.push(cells);
+        }
+        rows.extend(
+            new_rows
+                .into_iter()
+                .flat_map(|row| expand_row(row, options.expand_column)),
+        );
     }
     Ok(rows)
 }
 
+/// Rendered values of a dictionary column, each rendered on first use.
+struct DictionaryCache<'a> {
+    values: &'a ArrayRef,
+    keys: Vec<usize>,
+    rendered: Vec<Option<String>>,
+}
+
+impl<'a> DictionaryCache<'a> {
+    /// Returns `None` if `col` is not a dictionary.
+    fn new(col: &'a ArrayRef) -> Option<Self> {
+        let dictionary = col.as_any_dictionary_opt()?;
+        Some(Self {
+            values: dictionary.values(),
+            keys: dictionary.normalized_keys(),
+            rendered: vec![None; dictionary.values().len()],
+        })
+    }
+
+    /// Renders the cell at `row` like [`render_cell`] does.
+    fn render(
+        &mut self,
+        col: &ArrayRef,
+        row: usize,
+        context: &CellContext,
+    ) -> Result<String, FlightSqlLogicTestError> {
+        if !col.is_valid(row) {
+            return Ok(context.options.null_str().to_string());
+        }
+        let key = self.keys[row];
+        if let Some(rendered) = &self.rendered[key] {
+            return Ok(rendered.clone());
+        }
+        let rendered = render_cell(self.values, key, context)?;
+        self.rendered[key] = Some(rendered.clone());
+        Ok(rendered)
+    }
+}
+
+/// Checks that `batch` matches the `schema` of the result it belongs to.
+///
+/// Fails with [`FlightSqlLogicTestError::SchemaMismatch`] holding a diff of the fields.
+pub(crate) fn validate_batch_schema(
+    schema: &Schema,
+    batch: &RecordBatch,
+) -> Result<(), FlightSqlLogicTestError> {
+    if schema.contains(&batch.schema()) {
+        return Ok(());
+    }
+    let expected = schema_lines(schema);
+    let actual = schema_lines(&batch.schema());
+    // Schemas that only differ in ways `contains` ignores when printed still differ.
+    let diff = line_diff(&expected, &actual).unwrap_or(expected);
+    Err(FlightSqlLogicTestError::SchemaMismatch { diff })
+}
+
+/// Renders one line per field of `schema`, followed by the schema metadata if any.
+fn schema_lines(schema: &Schema) -> String {
+    let mut lines = String::new();
+    for field in schema.fields() {
+        let nullable = if field.is_nullable() { "" } else { " NOT NULL" };
+        lines.push_str(&format!(
+            "{}: {}{nullable}",
+            field.name(),
+            field.data_type()
+        ));
+        if !field.metadata().is_empty() {
+            lines.push_str(&format!(" {:?}", field.metadata()));
+        }
+        lines.push('\n');
+    }
+    if !schema.metadata().is_empty() {
+        lines.push_str(&format!("metadata: {:?}\n", schema.metadata()));
+    }
+    lines
+}
+
 macro_rules! get_row_value {
     ($array_type:ty, $column: ident, $row: ident) => {{
         let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
@@ -142,50 +635,320 @@ macro_rules! get_row_value {
 /// [NULL Values and empty strings]: https://duckdb.org/dev/sqllogictest/result_verification#null-values-and-empty-strings
 ///
 /// Floating numbers are rounded to have a consistent representation with the Postgres runner.
+/// Arrow has no bfloat16 type, so bfloat16 values are expected to arrive widened to `Float32`
+/// and are rendered like any other `Float32` value.
 pub fn cell_to_string(col: &ArrayRef, row: usize) -> Result<String, FlightSqlLogicTestError> {
+    cell_to_string_with_options(col, row, &ConvertOptions::default())
+}
+
+/// Normalizes the content of a single cell like [`cell_to_string`], using the given `options`.
+///
+/// The cell is never truncated, see [`ConvertOptions::with_max_cell_len`].
+pub fn cell_to_string_with_options(
+    col: &ArrayRef,
+    row: usize,
+    options: &ConvertOptions,
+) -> Result<String, FlightSqlLogicTestError> {
+    render_cell(col, row, &CellContext::new(options))
+}
+
+/// Truncates `cell` to `max_len` bytes, see [`ConvertOptions::with_max_cell_len`].
+fn truncate_cell(mut cell: String, max_len: Option<usize>) -> String {
+    let Some(max_len) = max_len.filter(|max_len| cell.len() > *max_len) else {
+        return cell;
+    };
+    let end = (0..=max_len)
+        .rev()
+        .find(|&index| cell.is_char_boundary(index))
+        .unwrap_or(0);
+    let truncated = cell.len() - end;
+    cell.truncate(end);
+    cell.push_str(&format!("...[truncated {truncated} bytes]"));
+    cell
+}
+
+/// Like [`cell_to_string`], but keeps the full precision of floats and decimals.
+///
+/// Results compared by sqllogictest are rounded to 12 decimal places, this returns the exact
+/// value for reporting, together with any trailing zeros of the decimal scale.
+pub fn cell_to_string_raw(col: &ArrayRef, row: usize) -> Result<String, FlightSqlLogicTestError> {
+    let options = ConvertOptions::default();
+    let context = CellContext {
+        raw_numbers: true,
+        ..CellContext::new(&options)
+    };
+    render_cell(col, row, &context)
+}
+
+fn render_cell(
+    col: &ArrayRef,
+    row: usize,
+    context: &CellContext,
+) -> Result<String, FlightSqlLogicTestError> {
+    let options = context.options;
     if !col.is_valid(row) {
         // represent any null value with the string "NULL"
-        Ok(NULL_STR.to_string())
+        Ok(options.null_str().to_string())
     } else {
         match col.data_type() {
-            DataType::Null => Ok(NULL_STR.to_string()),
-            DataType::Boolean => Ok(bool_to_str(get_row_value!(BooleanArray, col, row))),
-            DataType::Float16 => Ok(f16_to_str(get_row_value!(Float16Array, col, row))),
-            DataType::Float32 => Ok(f32_to_str(get_row_value!(Float32Array, col, row))),
+            DataType::Null => Ok(options.null_str().to_string()),
+            DataType::Boolean => {
+                let value = get_row_value!(BooleanArray, col, row);
+                Ok(options.bool_format.render(value))
+            }
+            DataType::Float16 => {
+                let value = get_row_value!(Float16Array, col, row);
+                if context.raw_numbers {
+                    raw_float_to_str(value, value.to_f64())
+                } else {
+                    f16_to_str(value, options.number_notation)
+                }
+            }
+            DataType::Float32 => {
+                let value = get_row_value!(Float32Array, col, row);
+                if context.raw_numbers {
+                    raw_float_to_str(value, value.into())
+                } else {
+                    f32_to_str(value, options.number_notation)
+                }
+            }
             DataType::Float64 => {
-                let result = get_row_value!(Float64Array, col, row);
-                Ok(f64_to_str(result))
+                let value = get_row_value!(Float64Array, col, row);
+                if context.raw_numbers {
+                    raw_float_to_str(value, value)
+                } else {
+                    f64_to_str(value, options.number_notation)
+                }
             }
-            DataType::Decimal128(_, scale) => {
+            DataType::Decimal128(precision, scale) => {
                 let value = get_row_value!(Decimal128Array, col, row);
-                Ok(decimal_128_to_str(value, *scale))
+                if context.raw_numbers {
+                    Ok(Decimal128Type::format_decimal(value, *precision, *scale))
+                } else {
+                    decimal_128_to_str(value, *scale, options.number_notation)
+                }
             }
-            DataType::Decimal256(_, scale) => {
+            DataType::Decimal256(precision, scale) => {
                 let value = get_row_value!(Decimal256Array, col, row);
-                Ok(decimal_256_to_str(value, *scale))
+                if context.raw_numbers {
+                    Ok(Decimal256Type::format_decimal(value, *precision, *scale))
+                } else {
+                    decimal_256_to_str(value, *scale, options.number_notation)
+                }
+            }
+            DataType::LargeUtf8 => Ok(text_to_str(
+                get_row_value!(LargeStringArray, col, row),
+                context,
+            )),
+            DataType::Utf8 => Ok(text_to_str(get_row_value!(StringArray, col, row), context)),
+            DataType::Utf8View => Ok(text_to_str(
+                get_row_value!(StringViewArray, col, row),
+                context,
+            )),
+            DataType::Dictionary(_, _) => downcast_dictionary_array!(
+                col => match col.key(row) {
+                    // Look up the single key rather than normalizing all keys of the column,
+                    // which would make converting a dictionary column quadratic.
+                    Some(key) => render_cell(col.values(), key, context),
+                    None => Ok(options.null_str().to_string()),
+                },
+                t => Err(FlightSqlLogicTestError::Other(format!(
+                    "Unsupported dictionary type {t}"
+                ))),
+            ),
+            DataType::Timestamp(unit, Some(_)) => {
+                let timezone = match &options.timestamp_display_timezone {
+                    TimestampDisplayTimezone::AsStored => {
+                        return format_value(col.as_ref(), row, options);
+                    }
+                    TimestampDisplayTimezone::Utc => "+00:00",
+                    TimestampDisplayTimezone::Fixed(timezone) => timezone.as_str(),
+                };
+                // Timestamp values are stored in UTC, so converting only swaps the timezone.
+                let converted = cast(
+                    &col.slice(row, 1),
+                    &DataType::Timestamp(*unit, Some(timezone.into())),
+                )?;
+                format_value(converted.as_ref(), 0, options)
+            }
+            _ => format_value(col.as_ref(), row, options),
+        }
+    }
+}
+
+/// Formats a cell using arrow's display formatter.
+///
+/// Nested nulls, like null list elements, are rendered as the configured null string, and
+/// nested booleans in the configured [`BoolFormat`]. Arrow formats numbers without grouping
+/// separators and never consults the process locale, so [`FormatOptions`] has nothing to pin
+/// for that.
+fn format_value(
+    col: &dyn Array,
+    row: usize,
+    options: &ConvertOptions,
+) -> Result<String, FlightSqlLogicTestError> {
+    let factory = NestedFormatterFactory { options };
+    let format_options = FormatOptions::default()
+        .with_null(options.null_str())
+        .with_formatter_factory(Some(&factory));
+
+    let f = make_formatter(col, &format_options)?;
+
+    Ok(f.value(row).to_string())
+}
+
+/// Creates a formatter for `array`, preferring the formatter factory of `options`.
+///
+/// Arrow only consults the factory for the children of nested values, not for the array
+/// passed to [`ArrayFormatter::try_new`] or for dictionary values.
+fn make_formatter<'a>(
+    array: &'a dyn Array,
+    options: &FormatOptions<'a>,
+) -> Result<ArrayFormatter<'a>, ArrowError> {
+    let formatter = match options.formatter_factory() {
+        Some(factory) => factory.create_array_formatter(array, options, None)?,
+        None => None,
+    };
+    formatter.map_or_else(|| ArrayFormatter::try_new(array, options), Ok)
+}
+
+/// Formats the nested values whose rendering is configured by [`ConvertOptions`]: booleans
+/// in the configured [`BoolFormat`], and floats and decimals in scientific
+/// [`NumberNotation`]. Also formats the values of dictionaries and list views, which arrow
+/// formats without the factory or not at all.
+///
+/// Everything else, like nested text and floats in plain notation, keeps arrow's formatting.
+#[derive(Debug)]
+struct NestedFormatterFactory<'o> {
+    options: &'o ConvertOptions,
+}
+
+impl ArrayFormatterFactory for NestedFormatterFactory<'_> {
+    fn create_array_formatter<'a>(
+        &self,
+        array: &'a dyn Array,
+        options: &FormatOptions<'a>,
+        _field: Option<&'a Field>,
+    ) -> Result<Option<ArrayFormatter<'a>>, ArrowError> {
+        let null = options.null();
+        let scientific = matches!(
+            self.options.number_notation,
+            NumberNotation::Scientific { .. }
+        );
+        let format: Box<dyn DisplayIndex + 'a> = match array.data_type() {
+            DataType::Boolean => Box::new(CellDisplay {
+                array: make_array(array.to_data()),
+                options: self.options.clone(),
+            }),
+            DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+                if scientific =>
+            {
+                Box::new(CellDisplay {
+                    array: make_array(array.to_data()),
+                    options: self.options.clone(),
+                })
             }
-            DataType::LargeUtf8 => Ok(varchar_to_str(get_row_value!(LargeStringArray, col, row))),
-            DataType::Utf8 => Ok(varchar_to_str(get_row_value!(StringArray, col, row))),
-            DataType::Utf8View => Ok(varchar_to_str(get_row_value!(StringViewArray, col, row))),
             DataType::Dictionary(_, _) => {
-                let dict = col.as_any_dictionary();
-                let key = dict.normalized_keys()[row];
-                Ok(cell_to_string(dict.values(), key)?)
+                let dictionary = array.as_any_dictionary();
+                Box::new(DictionaryDisplay {
+                    array,
+                    keys: dictionary.normalized_keys(),
+                    values: make_formatter(dictionary.values().as_ref(), options)?,
+                    null,
+                })
+            }
+            DataType::ListView(_) => {
+                let list = array.as_list_view::<i32>();
+                Box::new(ListViewDisplay {
+                    list,
+                    values: make_formatter(list.values().as_ref(), options)?,
+                    null,
+                })
             }
-            _ => {
-                let format_options = FormatOptions::default();
+            DataType::LargeListView(_) => {
+                let list = array.as_list_view::<i64>();
+                Box::new(ListViewDisplay {
+                    list,
+                    values: make_formatter(list.values().as_ref(), options)?,
+                    null,
+                })
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(ArrayFormatter::new(format, options.safe())))
+    }
+}
 
-                let f = ArrayFormatter::try_new(col.as_ref(), &format_options)?;
+/// Renders nested values with [`render_cell`].
+struct CellDisplay {
+    array: ArrayRef,
+    options: ConvertOptions,
+}
 
-                Ok(f.value(row).to_string())
+impl DisplayIndex for CellDisplay {
+    fn write(&self, idx: usize, f: &mut dyn fmt::Write) -> FormatResult {
+        let cell = render_cell(&self.array, idx, &CellContext::new(&self.options))
+            .map_err(|e| FormatError::Arrow(ArrowError::ComputeError(e.to_string())))?;
+        f.write_str(&cell)?;
+        Ok(())
+    }
+}
+
+struct DictionaryDisplay<'a> {
+    array: &'a dyn Array,
+    keys: Vec<usize>,
+    values: ArrayFormatter<'a>,
+    null: &'a str,
+}
+
+impl DisplayIndex for DictionaryDisplay<'_> {
+    fn write(&self, idx: usize, f: &mut dyn fmt::Write) -> FormatResult {
+        if self.array.is_null(idx) {
+            f.write_str(self.null)?;
+        } else {
+            write!(f, "{}", self.values.value(self.keys[idx]))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats list views like arrow formats lists.
+struct ListViewDisplay<'a, O: OffsetSizeTrait> {
+    list: &'a GenericListViewArray<O>,
+    values: ArrayFormatter<'a>,
+    null: &'a str,
+}
+
+impl<O: OffsetSizeTrait> DisplayIndex for ListViewDisplay<'_, O> {
+    fn write(&self, idx: usize, f: &mut dyn fmt::Write) -> FormatResult {
+        if self.list.is_null(idx) {
+            f.write_str(self.null)?;
+            return Ok(());
+        }
+        let start = self.list.value_offsets()[idx].as_usize();
+        let end = start + self.list.value_sizes()[idx].as_usize();
+        f.write_char('[')?;
+        for (position, value) in (start..end).enumerate() {
+            if position > 0 {
+                f.write_str(", ")?;
             }
+            write!(f, "{}", self.values.value(value))?;
         }
+        f.write_char(']')?;
+        Ok(())
     }
 }
 
 /// Represents a constant for NULL string in your database.
 pub const NULL_STR: &str = "NULL";
 
+/// Represents a constant for empty strings in your database.
+pub const EMPTY_STR: &str = "(empty)";
+
 pub(crate) fn bool_to_str(value: bool) -> String {
     if value {
         "true".to_string()
@@ -196,78 +959,163 @@ pub(crate) fn bool_to_str(value: bool) -> String {
 
 pub(crate) fn varchar_to_str(value: &str) -> String {
     if value.is_empty() {
-        "(empty)".to_string()
+        EMPTY_STR.to_string()
     } else {
         // Escape nulls so that github renders them correctly in the webui
         value.trim_end_matches('\n').replace("\u{0000}", "\\0")
     }
 }
 
-pub(crate) fn f16_to_str(value: f16) -> String {
+/// Renders a text value, quoting it as configured by [`ConvertOptions::with_text_quoting`].
+fn text_to_str(value: &str, context: &CellContext) -> String {
+    if value.is_empty() {
+        return context.empty_str.to_string();
+    }
+    let rendered = varchar_to_str(value);
+    let quote = match context.options.text_quoting {
+        TextQuoting::Off => false,
+        TextQuoting::Markers => {
+            rendered == context.options.null_str()
+                || rendered == context.empty_str
+                || (rendered.len() >= 2 && rendered.starts_with('"') && rendered.ends_with('"'))
+        }
+        TextQuoting::All => true,
+    };
+    if quote {
+        format!("\"{rendered}\"")
+    } else {
+        rendered
+    }
+}
+
+pub(crate) fn f16_to_str(
+    value: f16,
+    notation: NumberNotation,
+) -> Result<String, FlightSqlLogicTestError> {
     if value.is_nan() {
         // The sign of NaN can be different depending on platform.
         // So the string representation of NaN ignores the sign.
-        "NaN".to_string()
+        Ok("NaN".to_string())
     } else if value == f16::INFINITY {
-        "Infinity".to_string()
+        Ok("Infinity".to_string())
     } else if value == f16::NEG_INFINITY {
-        "-Infinity".to_string()
+        Ok("-Infinity".to_string())
     } else {
-        big_decimal_to_str(BigDecimal::from_str(&value.to_string()).unwrap(), None)
+        // f16's `Display` rounds to its own low precision, widen so the value renders
+        // like the same number stored as f32.
+        f32_to_str(value.to_f32(), notation)
     }
 }
 
-pub(crate) fn f32_to_str(value: f32) -> String {
+pub(crate) fn f32_to_str(
+    value: f32,
+    notation: NumberNotation,
+) -> Result<String, FlightSqlLogicTestError> {
     if value.is_nan() {
         // The sign of NaN can be different depending on platform.
         // So the string representation of NaN ignores the sign.
-        "NaN".to_string()
+        Ok("NaN".to_string())
     } else if value == f32::INFINITY {
-        "Infinity".to_string()
+        Ok("Infinity".to_string())
     } else if value == f32::NEG_INFINITY {
-        "-Infinity".to_string()
+        Ok("-Infinity".to_string())
     } else {
-        big_decimal_to_str(BigDecimal::from_str(&value.to_string()).unwrap(), None)
+        let decimal = parse_big_decimal(&value.to_string())?;
+        Ok(big_decimal_to_str(decimal, None, notation))
     }
 }
 
-pub(crate) fn f64_to_str(value: f64) -> String {
+pub(crate) fn f64_to_str(
+    value: f64,
+    notation: NumberNotation,
+) -> Result<String, FlightSqlLogicTestError> {
     if value.is_nan() {
         // The sign of NaN can be different depending on platform.
         // So the string representation of NaN ignores the sign.
-        "NaN".to_string()
+        Ok("NaN".to_string())
     } else if value == f64::INFINITY {
-        "Infinity".to_string()
+        Ok("Infinity".to_string())
     } else if value == f64::NEG_INFINITY {
-        "-Infinity".to_string()
+        Ok("-Infinity".to_string())
     } else {
-        big_decimal_to_str(BigDecimal::from_str(&value.to_string()).unwrap(), None)
+        let decimal = parse_big_decimal(&value.to_string())?;
+        Ok(big_decimal_to_str(decimal, None, notation))
     }
 }
 
-pub(crate) fn decimal_128_to_str(value: i128, scale: i8) -> String {
+/// Renders a float with its full precision, naming NaN and infinities like [`f64_to_str`].
+fn raw_float_to_str(
+    value: impl std::fmt::Display,
+    as_f64: f64,
+) -> Result<String, FlightSqlLogicTestError> {
+    if as_f64.is_finite() {
+        Ok(value.to_string())
+    } else {
+        f64_to_str(as_f64, NumberNotation::Plain)
+    }
+}
+
+pub(crate) fn decimal_128_to_str(
+    value: i128,
+    scale: i8,
+    notation: NumberNotation,
+) -> Result<String, FlightSqlLogicTestError> {
     let precision = u8::MAX; // does not matter
-    big_decimal_to_str(
-        BigDecimal::from_str(&Decimal128Type::format_decimal(value, precision, scale)).unwrap(),
-        None,
-    )
+    let decimal = parse_big_decimal(&Decimal128Type::format_decimal(value, precision, scale))?;
+    Ok(big_decimal_to_str(decimal, None, notation))
 }
 
-pub(crate) fn decimal_256_to_str(value: i256, scale: i8) -> String {
+pub(crate) fn decimal_256_to_str(
+    value: i256,
+    scale: i8,
+    notation: NumberNotation,
+) -> Result<String, FlightSqlLogicTestError> {
     let precision = u8::MAX; // does not matter
-    big_decimal_to_str(
-        BigDecimal::from_str(&Decimal256Type::format_decimal(value, precision, scale)).unwrap(),
-        None,
-    )
+    let decimal = parse_big_decimal(&Decimal256Type::format_decimal(value, precision, scale))?;
+    Ok(big_decimal_to_str(decimal, None, notation))
+}
+
+/// Parses a formatted number, failing instead of panicking if the formatter produced something
+/// `BigDecimal` can't read.
+///
+/// Every finite float and decimal is expected to parse, this guards against formatter edge
+/// cases.
+fn parse_big_decimal(formatted: &str) -> Result<BigDecimal, FlightSqlLogicTestError> {
+    BigDecimal::from_str(formatted).map_err(|e| {
+        FlightSqlLogicTestError::Other(format!("Cannot parse {formatted:?} as a decimal: {e}"))
+    })
 }
 
-/// Converts a `BigDecimal` to its plain string representation, optionally rounding to a specified number of decimal places.
+/// Converts a `BigDecimal` to its string representation in `notation`, optionally rounding to a specified number of decimal places.
 ///
-/// If `round_digits` is `None`, the value is rounded to 12 decimal places by default.
+/// If `round_digits` is `None`, the value is rounded to 12 decimal places by default. In
+/// scientific notation, the mantissa is rounded instead of the value.
 #[expect(clippy::needless_pass_by_value)]
-pub(crate) fn big_decimal_to_str(value: BigDecimal, round_digits: Option<i64>) -> String {
+pub(crate) fn big_decimal_to_str(
+    value: BigDecimal,
+    round_digits: Option<i64>,
+    notation: NumberNotation,
+) -> String {
+    let round_digits = round_digits.unwrap_or(12);
+    if let NumberNotation::Scientific { threshold } = notation {
+        let (digits, scale) = value.as_bigint_and_exponent();
+        // The power of ten of the leading digit, e.g. 2 for 123.4 and -3 for 0.00123.
+        let exponent = i64::try_from(value.digits()).unwrap_or(i64::MAX) - 1 - scale;
+        if !value.is_zero() && exponent.unsigned_abs() >= u64::from(threshold) {
+            let mut exponent = exponent;
+            let mut mantissa = BigDecimal::new(digits, scale + exponent)
+                .round(round_digits)
+                .normalized();
+            // Rounding can carry into a new digit, e.g. 9.9999999999999 to 10.
+            if mantissa.abs() >= BigDecimal::from(10) {
+                mantissa = (mantissa / BigDecimal::from(10)).normalized();
+                exponent += 1;
+            }
+            return format!("{}e{exponent}", mantissa.to_plain_string());
+        }
+    }
     // Round the value to limit the number of decimal places
-    let value = value.round(round_digits.unwrap_or(12)).normalized();
+    let value = value.round(round_digits).normalized();
     // Format the value to a string
     value.to_plain_string()
 }
@@ -294,40 +1142,1073 @@ pub(crate) fn big_decimal_to_str(value: BigDecimal, round_digits: Option<i64>) -
 ///   "|-- Projection: d.b, MAX(d.a) AS max_a",
 /// ]
 /// ```
-fn expand_row(mut row: Vec<String>) -> impl Iterator<Item = Vec<String>> {
+///
+/// Only the cell at `column` (the last cell if `None`) is expanded. The remaining cells are
+/// kept together in the first row, followed by one row per line of the expanded cell.
+fn expand_row(mut row: Vec<String>, column: Option<usize>) -> impl Iterator<Item = Vec<String>> {
     use itertools::Either;
     use std::iter::once;
 
-    // check last cell
-    if let Some(cell) = row.pop() {
-        let lines: Vec<_> = cell.split('\n').collect();
+    let index = column.unwrap_or(row.len().saturating_sub(1));
+    if index >= row.len() {
+        return Either::Left(once(row));
+    }
+
+    // check the expandable cell
+    let cell = row.remove(index);
+    let lines: Vec<_> = cell.split('\n').collect();
+
+    // no newlines in the expandable cell
+    if lines.len() < 2 {
+        row.insert(index, cell);
+        return Either::Left(once(row));
+    }
+
+    // form new rows with each additional line
+    let new_lines: Vec<_> = lines
+        .into_iter()
+        .enumerate()
+        .map(|(idx, l)| {
+            // replace any leading spaces with '-' as
+            // `sqllogictest` ignores whitespace differences
+            //
+            // See https://github.com/apache/datafusion/issues/6328
+            let content = l.trim_start();
+            let new_prefix = "-".repeat(l.len() - content.len());
+            // maintain for each line a number, so
+            // reviewing explain result changes is easier
+            let line_num = idx + 1;
+            vec![format!("{line_num:02}){new_prefix}{content}")]
+        })
+        .collect();
+
+    Either::Right(once(row).chain(new_lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow::array::{
+        Array, ArrayRef, AsArray, BooleanArray, BooleanBuilder, Decimal64Array, Decimal128Array,
+        DictionaryArray, Float64Array, Int8Array, Int64Array, LargeListViewArray, LargeStringArray,
+        ListArray, ListViewArray, MapBuilder, NullArray, RecordBatch, RecordBatchOptions,
+        StringArray, StringBuilder, StringViewArray, StructArray, TimestampSecondArray,
+        UInt64Array, UnionArray,
+    };
+    use arrow::buffer::{OffsetBuffer, ScalarBuffer};
+    use arrow::datatypes::{
+        DataType, Field, Fields, Float64Type, Int8Type, Int32Type, Int64Type, IntervalUnit, Schema,
+        TimeUnit, UnionFields,
+    };
+
+    use arrow::datatypes::i256;
+    use half::f16;
+    use sqllogictest::ColumnType;
+
+    use super::{
+        ArrowColumnType, BoolFormat, CellFormatter, ColumnSelector, ColumnTypeDetail,
+        ConvertOptions, NumberNotation, TextQuoting, TimestampDisplayTimezone, big_decimal_to_str,
+        cell_to_string, cell_to_string_raw, cell_to_string_with_options, column_type_details,
+        convert_batches, convert_batches_with_options, convert_schema_to_types,
+        convert_schema_to_types_with_options, decimal_128_to_str, decimal_256_to_str, f16_to_str,
+        f32_to_str, f64_to_str, parse_big_decimal,
+    };
+    use crate::error::FlightSqlLogicTestError;
+
+    fn plan_batch(plan_first: bool) -> RecordBatch {
+        let plan = Arc::new(StringArray::from(vec!["Sort: a\n  Scan: t"])) as _;
+        let label = Arc::new(StringArray::from(vec!["physical_plan"])) as _;
+        let (fields, columns) = if plan_first {
+            (vec!["plan", "plan_type"], vec![plan, label])
+        } else {
+            (vec!["plan_type", "plan"], vec![label, plan])
+        };
+        let schema = Schema::new(
+            fields
+                .into_iter()
+                .map(|name| Field::new(name, DataType::Utf8, false))
+                .collect::<Vec<_>>(),
+        );
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    #[test]
+    fn expands_last_column_by_default() {
+        let batch = plan_batch(false);
+        let rows = convert_batches(&batch.schema(), vec![batch]).unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["physical_plan".to_string()],
+                vec!["01)Sort: a".to_string()],
+                vec!["02)--Scan: t".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn decimals_at_extreme_values_and_scales_render() {
+        assert_eq!(
+            decimal_128_to_str(i128::MAX, 38, NumberNotation::Plain).unwrap(),
+            "1.701411834605"
+        );
+        assert_eq!(
+            decimal_128_to_str(i128::MIN, 0, NumberNotation::Plain).unwrap(),
+            "-170141183460469231731687303715884105728"
+        );
+        assert_eq!(
+            decimal_128_to_str(-123, -5, NumberNotation::Plain).unwrap(),
+            "-12300000"
+        );
+        assert_eq!(
+            decimal_128_to_str(1, i8::MAX, NumberNotation::Plain).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            decimal_256_to_str(i256::MIN, 76, NumberNotation::Plain).unwrap(),
+            "-5.789604461866"
+        );
+        assert_eq!(
+            decimal_256_to_str(i256::ONE, i8::MIN, NumberNotation::Plain).unwrap(),
+            format!("1{}", "0".repeat(128))
+        );
+    }
 
-        // no newlines in last cell
-        if lines.len() < 2 {
-            row.push(cell);
-            return Either::Left(once(row));
+    #[test]
+    fn dictionary_cache_matches_uncached_output() {
+        let values = StringArray::from(vec![Some("a"), None, Some(""), Some("b\nc")]);
+        let keys = Int8Array::from(vec![Some(0), Some(1), None, Some(2), Some(0), Some(3)]);
+        let dictionary = DictionaryArray::<Int8Type>::try_new(keys, Arc::new(values)).unwrap();
+        // The sliced column has keys that are no longer aligned with their rows.
+        let batch = RecordBatch::try_from_iter(vec![
+            ("d", Arc::new(dictionary.slice(0, 4)) as ArrayRef),
+            ("s", Arc::new(dictionary.slice(2, 4)) as ArrayRef),
+        ])
+        .unwrap();
+
+        for options in [
+            ConvertOptions::new(),
+            ConvertOptions::new()
+                .with_null_str("<null>")
+                .with_text_quoting(TextQuoting::All),
+        ] {
+            let uncached =
+                convert_batches_with_options(&batch.schema(), vec![batch.clone()], &options)
+                    .unwrap();
+            let options = options.with_dictionary_cache(true);
+            let cached =
+                convert_batches_with_options(&batch.schema(), vec![batch.clone()], &options)
+                    .unwrap();
+            assert_eq!(cached, uncached);
         }
+    }
 
-        // form new rows with each additional line
-        let new_lines: Vec<_> = lines
-            .into_iter()
-            .enumerate()
-            .map(|(idx, l)| {
-                // replace any leading spaces with '-' as
-                // `sqllogictest` ignores whitespace differences
-                //
-                // See https://github.com/apache/datafusion/issues/6328
-                let content = l.trim_start();
-                let new_prefix = "-".repeat(l.len() - content.len());
-                // maintain for each line a number, so
-                // reviewing explain result changes is easier
-                let line_num = idx + 1;
-                vec![format!("{line_num:02}){new_prefix}{content}")]
+    #[test]
+    fn null_type_renders_null_string_at_every_depth() {
+        let options = ConvertOptions::new().with_null_str("<null>");
+        let nulls: ArrayRef = Arc::new(NullArray::new(2));
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("n", DataType::Null, true)),
+            nulls.clone(),
+        )]));
+        let lists: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(DataType::Null, true)),
+            OffsetBuffer::from_lengths([2]),
+            nulls.clone(),
+            None,
+        ));
+
+        assert_eq!(
+            cell_to_string_with_options(&nulls, 0, &options).unwrap(),
+            "<null>"
+        );
+        assert_eq!(
+            cell_to_string_with_options(&structs, 1, &options).unwrap(),
+            "{n: <null>}"
+        );
+        assert_eq!(
+            cell_to_string_with_options(&lists, 0, &options).unwrap(),
+            "[<null>, <null>]"
+        );
+        assert_eq!(
+            convert_schema_to_types(&Fields::from(vec![Field::new("n", DataType::Null, true)])),
+            vec![ArrowColumnType::Text]
+        );
+    }
+
+    #[test]
+    fn large_numbers_render_without_grouping() {
+        let columns: Vec<(ArrayRef, &str)> = vec![
+            (
+                Arc::new(Int64Array::from(vec![i64::MIN])),
+                "-9223372036854775808",
+            ),
+            (
+                Arc::new(UInt64Array::from(vec![u64::MAX])),
+                "18446744073709551615",
+            ),
+            (
+                Arc::new(
+                    Decimal128Array::from(vec![i128::MAX / 1000])
+                        .with_precision_and_scale(38, 0)
+                        .unwrap(),
+                ),
+                "170141183460469231731687303715884105",
+            ),
+            (
+                Arc::new(
+                    Decimal64Array::from(vec![123_456_789_012_345_678])
+                        .with_precision_and_scale(18, 2)
+                        .unwrap(),
+                ),
+                "1234567890123456.78",
+            ),
+            (
+                Arc::new(Float64Array::from(vec![1e20])),
+                "100000000000000000000",
+            ),
+        ];
+        for (col, expected) in columns {
+            assert_eq!(cell_to_string(&col, 0).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn edge_floats_render() {
+        assert_eq!(f64_to_str(-0.0, NumberNotation::Plain).unwrap(), "0");
+        assert_eq!(f32_to_str(-0.0, NumberNotation::Plain).unwrap(), "0");
+        assert_eq!(
+            f64_to_str(f64::MIN_POSITIVE, NumberNotation::Plain).unwrap(),
+            "0"
+        );
+        assert_eq!(
+            f32_to_str(f32::MAX, NumberNotation::Plain).unwrap(),
+            "340282350000000000000000000000000000000"
+        );
+        let max = f64_to_str(f64::MAX, NumberNotation::Plain).unwrap();
+        assert!(
+            max.starts_with("17976931348623157") && max.len() == 309,
+            "{max}"
+        );
+        assert_eq!(
+            f64_to_str(-f64::MAX, NumberNotation::Plain).unwrap(),
+            format!("-{max}")
+        );
+    }
+
+    #[test]
+    fn scientific_notation_applies_from_threshold() {
+        let notation = NumberNotation::Scientific { threshold: 6 };
+        let render =
+            |value: &str| big_decimal_to_str(parse_big_decimal(value).unwrap(), None, notation);
+        assert_eq!(render("999999"), "999999");
+        assert_eq!(render("1000000"), "1e6");
+        assert_eq!(render("-1500000"), "-1.5e6");
+        assert_eq!(render("0.00001"), "0.00001");
+        assert_eq!(render("0.000001"), "1e-6");
+        assert_eq!(render("0.00000000000000123"), "1.23e-15");
+        assert_eq!(render("9.9999999999999e7"), "1e8");
+        assert_eq!(render("0"), "0");
+        assert_eq!(
+            f64_to_str(1e21, NumberNotation::Plain).unwrap(),
+            "1000000000000000000000"
+        );
+        assert_eq!(f64_to_str(1e21, notation).unwrap(), "1e21");
+        assert_eq!(f64_to_str(f64::NAN, notation).unwrap(), "NaN");
+        assert_eq!(decimal_128_to_str(-25, 8, notation).unwrap(), "-2.5e-7");
+    }
+
+    #[test]
+    fn number_notation_applies_to_nested_values() {
+        let options =
+            ConvertOptions::new().with_number_notation(NumberNotation::Scientific { threshold: 3 });
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Float64Type, _, _>(vec![
+            Some(vec![Some(12.5), Some(1234.0)]),
+        ]));
+        assert_eq!(
+            cell_to_string_with_options(&list, 0, &options).unwrap(),
+            "[12.5, 1.234e3]"
+        );
+    }
+
+    struct UppercaseText;
+
+    impl CellFormatter for UppercaseText {
+        fn format(
+            &self,
+            _field: &Field,
+            col: &ArrayRef,
+            row: usize,
+        ) -> Result<String, FlightSqlLogicTestError> {
+            let options = ConvertOptions::new();
+            let cell = cell_to_string_with_options(col, row, &options)?;
+            Ok(match col.data_type() {
+                DataType::Utf8 if col.is_valid(row) => cell.to_uppercase(),
+                _ => cell,
             })
-            .collect();
+        }
+    }
 
-        Either::Right(once(row).chain(new_lines))
-    } else {
-        Either::Left(once(row))
+    #[test]
+    fn cell_formatter_overrides_rendering() {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("abc"), None])),
+            ],
+        )
+        .unwrap();
+        let options = ConvertOptions::new().with_cell_formatter(Arc::new(UppercaseText));
+
+        let rows = convert_batches_with_options(&schema, vec![batch.clone()], &options).unwrap();
+        assert_eq!(rows, vec![vec!["1", "ABC"], vec!["2", "NULL"]]);
+        assert_eq!(
+            cell_to_string_with_options(batch.column(1), 0, &options).unwrap(),
+            "abc"
+        );
+    }
+
+    /// Renders `Int64` columns whose field metadata marks them as milliseconds in seconds.
+    struct MillisecondsHint;
+
+    impl CellFormatter for MillisecondsHint {
+        fn format(
+            &self,
+            field: &Field,
+            col: &ArrayRef,
+            row: usize,
+        ) -> Result<String, FlightSqlLogicTestError> {
+            let cell = cell_to_string_with_options(col, row, &ConvertOptions::new())?;
+            if col.is_null(row) || field.metadata().get("unit").map(String::as_str) != Some("ms") {
+                return Ok(cell);
+            }
+            let millis = col.as_primitive::<Int64Type>().value(row);
+            Ok(format!("{}s", millis as f64 / 1000.0))
+        }
+    }
+
+    #[test]
+    fn cell_formatter_reads_field_metadata() {
+        let unit = HashMap::from([("unit".to_string(), "ms".to_string())]);
+        let schema = Schema::new(vec![
+            Field::new("elapsed", DataType::Int64, true).with_metadata(unit),
+            Field::new("count", DataType::Int64, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(1500), None])),
+                Arc::new(Int64Array::from(vec![Some(1500), Some(2)])),
+            ],
+        )
+        .unwrap();
+        let options = ConvertOptions::new().with_cell_formatter(Arc::new(MillisecondsHint));
+
+        let rows = convert_batches_with_options(&schema, vec![batch], &options).unwrap();
+        assert_eq!(rows, vec![vec!["1.5s", "1500"], vec!["NULL", "2"]]);
+    }
+
+    #[test]
+    fn long_cells_are_truncated() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, false)]);
+        let column = StringArray::from(vec!["abcd", "abcde", "abcdef", "abcé"]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(column)]).unwrap();
+        let options = ConvertOptions::new().with_max_cell_len(5);
+
+        let rows = convert_batches_with_options(&schema, vec![batch.clone()], &options).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["abcd"],
+                vec!["abcde"],
+                vec!["abcde...[truncated 1 bytes]"],
+                vec!["abcé"],
+            ]
+        );
+        let options = options.with_max_cell_len(4);
+        let rows = convert_batches_with_options(&schema, vec![batch.clone()], &options).unwrap();
+        assert_eq!(rows[3], vec!["abc...[truncated 2 bytes]"]);
+        assert_eq!(
+            cell_to_string_with_options(batch.column(0), 3, &options).unwrap(),
+            "abcé"
+        );
+    }
+
+    /// Falls back to the built-in rendering with the options it is installed in.
+    struct FallbackWithOptions(ConvertOptions);
+
+    impl CellFormatter for FallbackWithOptions {
+        fn format(
+            &self,
+            _field: &Field,
+            col: &ArrayRef,
+            row: usize,
+        ) -> Result<String, FlightSqlLogicTestError> {
+            cell_to_string_with_options(col, row, &self.0)
+        }
+    }
+
+    #[test]
+    fn formatted_cells_are_truncated_once() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, false)]);
+        let column = StringArray::from(vec!["abcdefgh"]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(column)]).unwrap();
+        let options = ConvertOptions::new().with_max_cell_len(5);
+        let options = options
+            .clone()
+            .with_cell_formatter(Arc::new(FallbackWithOptions(options)));
+
+        let rows = convert_batches_with_options(&schema, vec![batch], &options).unwrap();
+        assert_eq!(rows, vec![vec!["abcde...[truncated 3 bytes]"]]);
+    }
+
+    #[test]
+    fn malformed_decimal_is_an_error() {
+        let error = parse_big_decimal("1.2e").unwrap_err();
+        assert!(
+            matches!(&error, FlightSqlLogicTestError::Other(message) if message.starts_with("Cannot parse \"1.2e\" as a decimal")),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn zero_column_batches_produce_no_rows() {
+        let schema = Arc::new(Schema::empty());
+        let options = RecordBatchOptions::new().with_row_count(Some(3));
+        let batch = RecordBatch::try_new_with_options(schema.clone(), vec![], &options).unwrap();
+
+        assert!(convert_batches(&schema, vec![batch]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn plan_in_first_column_is_not_expanded_by_default() {
+        let batch = plan_batch(true);
+        let rows = convert_batches(&batch.schema(), vec![batch]).unwrap();
+
+        assert_eq!(
+            rows,
+            vec![vec![
+                "Sort: a\n  Scan: t".to_string(),
+                "physical_plan".to_string()
+            ]]
+        );
+    }
+
+    #[test]
+    fn expands_configured_column() {
+        let batch = plan_batch(true);
+        let options = ConvertOptions::new().with_expand_column(0);
+        let rows = convert_batches_with_options(&batch.schema(), vec![batch], &options).unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec!["physical_plan".to_string()],
+                vec!["01)Sort: a".to_string()],
+                vec!["02)--Scan: t".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_expand_column_keeps_row() {
+        let batch = plan_batch(false);
+        let options = ConvertOptions::new().with_expand_column(5);
+        let rows = convert_batches_with_options(&batch.schema(), vec![batch], &options).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][1], "Sort: a\n  Scan: t");
+    }
+
+    #[test]
+    fn dictionary_cells_render_their_values() {
+        let keys = Int8Array::from(vec![Some(1), None, Some(0), Some(1)]);
+        let values = StringArray::from(vec!["a", "b"]);
+        let col: ArrayRef =
+            Arc::new(DictionaryArray::<Int8Type>::try_new(keys, Arc::new(values)).unwrap());
+
+        let cells = (0..col.len())
+            .map(|row| cell_to_string(&col, row).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(cells, vec!["b", "NULL", "a", "b"]);
+    }
+
+    fn render_dictionary(keys: Int8Array, values: StringArray) -> Vec<String> {
+        let col: ArrayRef =
+            Arc::new(DictionaryArray::<Int8Type>::try_new(keys, Arc::new(values)).unwrap());
+        let options = ConvertOptions::new().with_null_str("<null>");
+        (0..col.len())
+            .map(|row| cell_to_string_with_options(&col, row, &options).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dictionary_keys_of_null_values_render_as_null() {
+        let keys = Int8Array::from(vec![Some(0), Some(1), Some(0)]);
+        let values = StringArray::from(vec![None, Some("b")]);
+        assert_eq!(
+            render_dictionary(keys, values),
+            vec!["<null>", "b", "<null>"]
+        );
+    }
+
+    #[test]
+    fn null_dictionary_keys_render_as_null() {
+        // The key stored under a null slot is out of range and must not be looked up.
+        let keys = Int8Array::new(vec![7, 0].into(), Some(vec![false, true].into()));
+        let values = StringArray::from(vec!["a"]);
+        assert_eq!(render_dictionary(keys, values), vec!["<null>", "a"]);
+    }
+
+    #[test]
+    fn empty_dictionaries_render_nulls_and_no_rows() {
+        let keys = Int8Array::from(vec![None, None]);
+        let values = StringArray::from(Vec::<&str>::new());
+        assert_eq!(render_dictionary(keys, values), vec!["<null>", "<null>"]);
+
+        let keys = Int8Array::from(Vec::<i8>::new());
+        let values = StringArray::from(Vec::<&str>::new());
+        let col = DictionaryArray::<Int8Type>::try_new(keys, Arc::new(values)).unwrap();
+        let batch = RecordBatch::try_from_iter(vec![("d", Arc::new(col) as ArrayRef)]).unwrap();
+        let rows = convert_batches(&batch.schema(), vec![batch]).unwrap();
+        assert!(rows.is_empty());
+    }
+
+    fn render_text(values: Vec<Option<&str>>, quoting: TextQuoting) -> Vec<String> {
+        let col: ArrayRef = Arc::new(StringArray::from(values));
+        let options = ConvertOptions::new().with_text_quoting(quoting);
+        (0..col.len())
+            .map(|row| cell_to_string_with_options(&col, row, &options).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn text_matching_markers_is_ambiguous_by_default() {
+        let cells = render_text(
+            vec![Some("NULL"), None, Some("(empty)"), Some("")],
+            TextQuoting::Off,
+        );
+        assert_eq!(cells, vec!["NULL", "NULL", "(empty)", "(empty)"]);
+    }
+
+    #[test]
+    fn marker_quoting_quotes_colliding_text() {
+        let cells = render_text(
+            vec![Some("NULL"), None, Some("(empty)"), Some(""), Some("abc")],
+            TextQuoting::Markers,
+        );
+        assert_eq!(
+            cells,
+            vec!["\"NULL\"", "NULL", "\"(empty)\"", "(empty)", "abc"]
+        );
+    }
+
+    #[test]
+    fn marker_quoting_tells_null_text_and_quoted_text_apart() {
+        let cells = render_text(
+            vec![
+                None,
+                Some("NULL"),
+                Some("\"NULL\""),
+                Some("\"a"),
+                Some("\""),
+            ],
+            TextQuoting::Markers,
+        );
+        assert_eq!(cells, vec!["NULL", "\"NULL\"", "\"\"NULL\"\"", "\"a", "\""]);
+    }
+
+    #[test]
+    fn all_quoting_quotes_every_text_value() {
+        let cells = render_text(
+            vec![Some("NULL"), None, Some(""), Some("abc")],
+            TextQuoting::All,
+        );
+        assert_eq!(cells, vec!["\"NULL\"", "NULL", "(empty)", "\"abc\""]);
+    }
+
+    fn render_timestamp(timezone: Option<&str>, display: TimestampDisplayTimezone) -> String {
+        // 2024-07-01T12:00:00Z
+        let array = TimestampSecondArray::from(vec![1_719_835_200]).with_timezone_opt(timezone);
+        let col: ArrayRef = Arc::new(array);
+        let options = ConvertOptions::new().with_timestamp_display_timezone(display);
+        cell_to_string_with_options(&col, 0, &options).unwrap()
+    }
+
+    #[test]
+    fn zoned_timestamp_renders_in_stored_timezone_by_default() {
+        assert_eq!(
+            render_timestamp(Some("America/New_York"), TimestampDisplayTimezone::AsStored),
+            "2024-07-01T08:00:00-04:00"
+        );
+    }
+
+    #[test]
+    fn zoned_timestamp_converts_to_utc() {
+        assert_eq!(
+            render_timestamp(Some("America/New_York"), TimestampDisplayTimezone::Utc),
+            "2024-07-01T12:00:00Z"
+        );
+    }
+
+    #[test]
+    fn zoned_timestamp_converts_to_fixed_timezone() {
+        assert_eq!(
+            render_timestamp(
+                Some("America/New_York"),
+                TimestampDisplayTimezone::Fixed("Asia/Tokyo".to_string())
+            ),
+            "2024-07-01T21:00:00+09:00"
+        );
+        assert_eq!(
+            render_timestamp(
+                Some("America/New_York"),
+                TimestampDisplayTimezone::Fixed("+05:30".to_string())
+            ),
+            "2024-07-01T17:30:00+05:30"
+        );
+    }
+
+    #[test]
+    fn naive_timestamp_is_not_converted() {
+        assert_eq!(
+            render_timestamp(None, TimestampDisplayTimezone::Utc),
+            "2024-07-01T12:00:00"
+        );
+    }
+
+    #[test]
+    fn f16_renders_like_widened_f32() {
+        for value in [0.1, 1.5, -2.75, 65504.0, 0.000061] {
+            let value = f16::from_f32(value);
+            assert_eq!(
+                f16_to_str(value, NumberNotation::Plain).unwrap(),
+                f32_to_str(value.to_f32(), NumberNotation::Plain).unwrap()
+            );
+        }
+        assert_eq!(
+            f16_to_str(f16::from_f32(0.1), NumberNotation::Plain).unwrap(),
+            "0.099975586"
+        );
+    }
+
+    #[test]
+    fn f16_special_values() {
+        assert_eq!(f16_to_str(f16::NAN, NumberNotation::Plain).unwrap(), "NaN");
+        assert_eq!(f16_to_str(-f16::NAN, NumberNotation::Plain).unwrap(), "NaN");
+        assert_eq!(
+            f16_to_str(f16::INFINITY, NumberNotation::Plain).unwrap(),
+            "Infinity"
+        );
+        assert_eq!(
+            f16_to_str(f16::NEG_INFINITY, NumberNotation::Plain).unwrap(),
+            "-Infinity"
+        );
+    }
+
+    fn two_text_columns() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("generated", DataType::Utf8, false),
+            Field::new("authored", DataType::Utf8, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec!["", "a"])),
+                Arc::new(StringArray::from(vec!["", "b"])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn column_empty_str_overrides_global_marker() {
+        let batch = two_text_columns();
+        let options = ConvertOptions::new()
+            .with_empty_str("<empty>")
+            .with_column_empty_str(ColumnSelector::Name("authored".to_string()), "");
+        let rows = convert_batches_with_options(&batch.schema(), vec![batch], &options).unwrap();
+
+        assert_eq!(rows[0], vec!["<empty>", ""]);
+        assert_eq!(rows[1], vec!["a", "b"]);
+    }
+
+    #[test]
+    fn column_empty_str_selects_by_index() {
+        let batch = two_text_columns();
+        let options = ConvertOptions::new().with_column_empty_str(ColumnSelector::Index(0), "");
+        let rows = convert_batches_with_options(&batch.schema(), vec![batch], &options).unwrap();
+
+        assert_eq!(rows[0], vec!["", "(empty)"]);
+    }
+
+    #[test]
+    fn nested_nulls_render_as_null_str() {
+        let col: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            None,
+        ]));
+
+        assert_eq!(cell_to_string(&col, 0).unwrap(), "[1, NULL, 3]");
+        assert_eq!(cell_to_string(&col, 1).unwrap(), "NULL");
+
+        let options = ConvertOptions::new().with_null_str("<null>");
+        assert_eq!(
+            cell_to_string_with_options(&col, 0, &options).unwrap(),
+            "[1, <null>, 3]"
+        );
+        assert_eq!(
+            cell_to_string_with_options(&col, 1, &options).unwrap(),
+            "<null>"
+        );
+    }
+
+    #[test]
+    fn intervals_and_durations_map_to_text() {
+        let fields = Fields::from(vec![
+            Field::new("ym", DataType::Interval(IntervalUnit::YearMonth), true),
+            Field::new("dt", DataType::Interval(IntervalUnit::DayTime), true),
+            Field::new("mdn", DataType::Interval(IntervalUnit::MonthDayNano), true),
+            Field::new("s", DataType::Duration(TimeUnit::Second), true),
+            Field::new("ns", DataType::Duration(TimeUnit::Nanosecond), true),
+        ]);
+
+        assert_eq!(
+            convert_schema_to_types(&fields),
+            vec![ArrowColumnType::Text; 5]
+        );
+    }
+
+    #[test]
+    fn column_type_chars_round_trip() {
+        for column_type in [
+            ArrowColumnType::Boolean,
+            ArrowColumnType::DateTime,
+            ArrowColumnType::Integer,
+            ArrowColumnType::Float,
+            ArrowColumnType::Text,
+            ArrowColumnType::Time,
+            ArrowColumnType::Timestamp,
+            ArrowColumnType::Another,
+        ] {
+            let char = column_type.to_char();
+            assert_eq!(ArrowColumnType::from_char(char), Some(column_type));
+        }
+        assert_eq!(
+            ArrowColumnType::from_char('?'),
+            Some(ArrowColumnType::Another)
+        );
+    }
+
+    #[test]
+    fn times_map_to_their_own_type_when_enabled() {
+        let fields = Fields::from(vec![
+            Field::new("d32", DataType::Date32, true),
+            Field::new("d64", DataType::Date64, true),
+            Field::new("t32", DataType::Time32(TimeUnit::Second), true),
+            Field::new("t64", DataType::Time64(TimeUnit::Nanosecond), true),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Second, None), true),
+        ]);
+        let chars =
+            |types: Vec<ArrowColumnType>| types.iter().map(ColumnType::to_char).collect::<String>();
+
+        assert_eq!(chars(convert_schema_to_types(&fields)), "DDDDP");
+        let options = ConvertOptions::new().with_time_column_type(true);
+        assert_eq!(
+            chars(convert_schema_to_types_with_options(&fields, &options)),
+            "DDHHP"
+        );
+    }
+
+    #[test]
+    fn column_type_details_keep_decimal_precision_and_width() {
+        let fields = Fields::from(vec![
+            Field::new("d", DataType::Decimal128(38, 10), true),
+            Field::new("b", DataType::FixedSizeBinary(16), true),
+            Field::new("s", DataType::Utf8, true),
+        ]);
+        assert_eq!(
+            column_type_details(&fields),
+            vec![
+                ColumnTypeDetail {
+                    name: "d".to_string(),
+                    column_type: ArrowColumnType::Float,
+                    data_type: DataType::Decimal128(38, 10),
+                    precision: Some(38),
+                    scale: Some(10),
+                    byte_width: Some(16),
+                    children: vec![],
+                },
+                ColumnTypeDetail {
+                    name: "b".to_string(),
+                    column_type: ArrowColumnType::Another,
+                    data_type: DataType::FixedSizeBinary(16),
+                    precision: None,
+                    scale: None,
+                    byte_width: Some(16),
+                    children: vec![],
+                },
+                ColumnTypeDetail {
+                    name: "s".to_string(),
+                    column_type: ArrowColumnType::Text,
+                    data_type: DataType::Utf8,
+                    precision: None,
+                    scale: None,
+                    byte_width: None,
+                    children: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn column_type_details_describe_nested_children() {
+        let int_detail = |name: &str| ColumnTypeDetail {
+            name: name.to_string(),
+            column_type: ArrowColumnType::Integer,
+            data_type: DataType::Int32,
+            precision: None,
+            scale: None,
+            byte_width: Some(4),
+            children: vec![],
+        };
+        let struct_fields = Fields::from(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let fields = Fields::from(vec![
+            Field::new_list("l", Field::new_list_field(DataType::Int32, true), true),
+            Field::new_struct("s", struct_fields.clone(), true),
+        ]);
+
+        let details = column_type_details(&fields);
+        assert_eq!(details[0].column_type, ArrowColumnType::Another);
+        assert_eq!(details[0].children, vec![int_detail("item")]);
+        assert_eq!(details[1].data_type, DataType::Struct(struct_fields));
+        assert_eq!(
+            details[1].children,
+            vec![
+                int_detail("a"),
+                ColumnTypeDetail {
+                    name: "b".to_string(),
+                    column_type: ArrowColumnType::Text,
+                    data_type: DataType::Utf8,
+                    precision: None,
+                    scale: None,
+                    byte_width: None,
+                    children: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bool_format_applies_at_every_depth() {
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+        ]));
+        let booleans: ArrayRef = Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)]));
+        let list_of_booleans: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(DataType::Boolean, true)),
+            OffsetBuffer::from_lengths([3]),
+            booleans.clone(),
+            None,
+        ));
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![
+            (Arc::new(Field::new("b", DataType::Boolean, true)), booleans),
+            (Arc::new(Field::new("l", list.data_type().clone(), true)), {
+                let lists = vec![Some(vec![Some(1)]), Some(vec![]), None];
+                Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(lists)) as ArrayRef
+            }),
+        ]));
+
+        for (format, expected_list, expected_struct) in [
+            (
+                BoolFormat::TrueFalse,
+                "[true, NULL, false]",
+                "{b: true, l: [1]}",
+            ),
+            (BoolFormat::Short, "[t, NULL, f]", "{b: t, l: [1]}"),
+            (BoolFormat::Numeric, "[1, NULL, 0]", "{b: 1, l: [1]}"),
+        ] {
+            let options = ConvertOptions::new().with_bool_format(format);
+            assert_eq!(
+                cell_to_string_with_options(&list_of_booleans, 0, &options).unwrap(),
+                expected_list
+            );
+            assert_eq!(
+                cell_to_string_with_options(&structs, 0, &options).unwrap(),
+                expected_struct
+            );
+        }
+
+        let options = ConvertOptions::new().with_bool_format(BoolFormat::Short);
+        assert_eq!(
+            cell_to_string_with_options(&structs, 1, &options).unwrap(),
+            "{b: NULL, l: []}"
+        );
+        assert_eq!(
+            cell_to_string_with_options(&structs, 2, &options).unwrap(),
+            "{b: f, l: NULL}"
+        );
+    }
+
+    #[test]
+    fn bool_format_applies_in_every_nested_container() {
+        let booleans: ArrayRef = Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)]));
+        let list_view: ArrayRef = Arc::new(ListViewArray::new(
+            Arc::new(Field::new_list_field(DataType::Boolean, true)),
+            ScalarBuffer::from(vec![0]),
+            ScalarBuffer::from(vec![3]),
+            booleans.clone(),
+            None,
+        ));
+        let large_list_view: ArrayRef = Arc::new(LargeListViewArray::new(
+            Arc::new(Field::new_list_field(DataType::Boolean, true)),
+            ScalarBuffer::from(vec![0]),
+            ScalarBuffer::from(vec![3]),
+            booleans.clone(),
+            None,
+        ));
+        let mut maps = MapBuilder::new(None, StringBuilder::new(), BooleanBuilder::new());
+        maps.keys().append_value("a");
+        maps.values().append_value(true);
+        maps.keys().append_value("b");
+        maps.values().append_value(false);
+        maps.append(true).unwrap();
+        let maps: ArrayRef = Arc::new(maps.finish());
+        let unions: ArrayRef = Arc::new(
+            UnionArray::try_new(
+                UnionFields::new(vec![0], vec![Field::new("b", DataType::Boolean, true)]),
+                ScalarBuffer::from(vec![0]),
+                None,
+                vec![Arc::new(BooleanArray::from(vec![false]))],
+            )
+            .unwrap(),
+        );
+        let dictionary = DictionaryArray::<Int8Type>::new(
+            Int8Array::from(vec![Some(1), None, Some(0)]),
+            Arc::new(BooleanArray::from(vec![true, false])),
+        );
+        let list_of_dictionaries: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(dictionary.data_type().clone(), true)),
+            OffsetBuffer::from_lengths([3]),
+            Arc::new(dictionary),
+            None,
+        ));
+        let dictionary_of_lists: ArrayRef = Arc::new(DictionaryArray::<Int8Type>::new(
+            Int8Array::from(vec![0]),
+            Arc::new(ListArray::new(
+                Arc::new(Field::new_list_field(DataType::Boolean, true)),
+                OffsetBuffer::from_lengths([3]),
+                booleans,
+                None,
+            )),
+        ));
+
+        for (format, t, f) in [
+            (BoolFormat::TrueFalse, "true", "false"),
+            (BoolFormat::Short, "t", "f"),
+            (BoolFormat::Numeric, "1", "0"),
+        ] {
+            let options = ConvertOptions::new().with_bool_format(format);
+            let render =
+                |array: &ArrayRef| cell_to_string_with_options(array, 0, &options).unwrap();
+            assert_eq!(render(&list_view), format!("[{t}, NULL, {f}]"));
+            assert_eq!(render(&large_list_view), format!("[{t}, NULL, {f}]"));
+            assert_eq!(render(&maps), format!("{{a: {t}, b: {f}}}"));
+            assert_eq!(render(&unions), format!("{{b={f}}}"));
+            assert_eq!(render(&list_of_dictionaries), format!("[{f}, NULL, {t}]"));
+            assert_eq!(render(&dictionary_of_lists), format!("[{t}, NULL, {f}]"));
+        }
+    }
+
+    #[test]
+    fn nested_text_and_floats_keep_arrow_formatting() {
+        let texts: ArrayRef = Arc::new(StringArray::from(vec!["", "a", "NULL"]));
+        let floats: ArrayRef = Arc::new(Float64Array::from(vec![0.1 + 0.2, 1.0]));
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![(
+            Arc::new(Field::new("s", DataType::Utf8, false)),
+            texts.clone(),
+        )]));
+        let list_of_texts: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(DataType::Utf8, false)),
+            OffsetBuffer::from_lengths([3]),
+            texts,
+            None,
+        ));
+        let list_of_floats: ArrayRef = Arc::new(ListArray::new(
+            Arc::new(Field::new_list_field(DataType::Float64, false)),
+            OffsetBuffer::from_lengths([2]),
+            floats,
+            None,
+        ));
+
+        for options in [
+            ConvertOptions::new(),
+            ConvertOptions::new().with_text_quoting(TextQuoting::All),
+        ] {
+            let render =
+                |array: &ArrayRef, row| cell_to_string_with_options(array, row, &options).unwrap();
+            assert_eq!(render(&list_of_texts, 0), "[, a, NULL]");
+            assert_eq!(render(&list_of_floats, 0), "[0.30000000000000004, 1.0]");
+            assert_eq!(render(&structs, 0), "{s: }");
+            assert_eq!(render(&structs, 1), "{s: a}");
+        }
+    }
+
+    #[test]
+    fn raw_cells_keep_full_precision() {
+        let decimals: ArrayRef = Arc::new(
+            Decimal128Array::from(vec![12_345_678_901_234_567_890_i128])
+                .with_precision_and_scale(38, 18)
+                .unwrap(),
+        );
+        assert_eq!(cell_to_string(&decimals, 0).unwrap(), "12.345678901235");
+        assert_eq!(
+            cell_to_string_raw(&decimals, 0).unwrap(),
+            "12.345678901234567890"
+        );
+
+        let floats: ArrayRef = Arc::new(Float64Array::from(vec![0.1 + 0.2, f64::NAN]));
+        assert_eq!(cell_to_string(&floats, 0).unwrap(), "0.3");
+        assert_eq!(
+            cell_to_string_raw(&floats, 0).unwrap(),
+            "0.30000000000000004"
+        );
+        assert_eq!(cell_to_string_raw(&floats, 1).unwrap(), "NaN");
+    }
+
+    #[test]
+    fn string_encodings_render_identically() {
+        let values = vec![Some("abc"), Some(""), Some("x\n"), Some("a\0b"), None];
+        let expected = vec!["abc", "(empty)", "x", "a\\0b", "NULL"];
+        // Long values and a slice make the view array use its data buffers and offsets.
+        let padded = [
+            vec![Some("a value longer than twelve bytes")],
+            values.clone(),
+        ]
+        .concat();
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(values.clone())),
+            Arc::new(LargeStringArray::from(values.clone())),
+            Arc::new(StringViewArray::from(values)),
+            Arc::new(StringViewArray::from(padded).slice(1, expected.len())),
+        ];
+
+        for array in arrays {
+            let cells = (0..array.len())
+                .map(|row| cell_to_string(&array, row).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(cells, expected, "{}", array.data_type());
+        }
     }
 }
@@ -0,0 +1,339 @@
+use sqllogictest::{ColumnType, DBOutput};
+
+use crate::column::ArrowColumnType;
+
+/// Describes the first difference found between two results.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Mismatch {
+    #[error("Output kind mismatch: expected {expected}, got {actual}")]
+    Kind {
+        actual: &'static str,
+        expected: &'static str,
+    },
+
+    #[error("Statement count mismatch: expected {expected}, got {actual}")]
+    StatementCount { actual: u64, expected: u64 },
+
+    #[error("Column types mismatch: expected {expected:?}, got {actual:?}")]
+    Types { actual: String, expected: String },
+
+    #[error("Row count mismatch: expected {expected} rows, got {actual}")]
+    RowCount { actual: usize, expected: usize },
+
+    #[error("Column count mismatch in row {row}: expected {expected} columns, got {actual}")]
+    ColumnCount {
+        row: usize,
+        actual: usize,
+        expected: usize,
+    },
+
+    #[error("Value mismatch at row {row}, column {column}: expected {expected:?}, got {actual:?}")]
+    Cell {
+        row: usize,
+        column: usize,
+        actual: String,
+        expected: String,
+    },
+}
+
+/// Compares converted rows, allowing float columns to differ by up to `epsilon`.
+///
+/// `types` are the column types of the result, as returned by
+/// [`convert_schema_to_types`](crate::column::convert_schema_to_types). Cells of
+/// [`ArrowColumnType::Float`] columns that both parse as numbers are equal when
+/// `|actual - expected| <= epsilon * max(1, |actual|, |expected|)`, i.e. `epsilon` is an
+/// absolute tolerance for values below 1 and a relative one above. All other cells, including
+/// `NULL` and non-numeric float cells, must match exactly.
+///
+/// This is meant for harnesses doing approximate comparison on their own; [`AsyncDB::run`]
+/// always produces exact strings.
+///
+/// [`AsyncDB::run`]: sqllogictest::AsyncDB::run
+pub fn compare_rows_with_tolerance(
+    actual: &[Vec<String>],
+    expected: &[Vec<String>],
+    types: &[ArrowColumnType],
+    epsilon: f64,
+) -> Result<(), Mismatch> {
+    compare_rows(actual, expected, |column, actual, expected| {
+        if types.get(column) == Some(&ArrowColumnType::Float) {
+            floats_match(actual, expected, epsilon)
+        } else {
+            actual == expected
+        }
+    })
+}
+
+/// Compares two outputs as produced by [`AsyncDB::run`], e.g. of two engines running the same
+/// query, reporting the first difference.
+///
+/// Outputs must be of the same kind. Rows must have the same type line and exactly equal
+/// cells, and statements the same count.
+///
+/// [`AsyncDB::run`]: sqllogictest::AsyncDB::run
+pub fn compare_outputs(
+    actual: &DBOutput<ArrowColumnType>,
+    expected: &DBOutput<ArrowColumnType>,
+) -> Result<(), Mismatch> {
+    match (actual, expected) {
+        (
+            DBOutput::Rows {
+                types: actual_types,
+                rows: actual_rows,
+            },
+            DBOutput::Rows {
+                types: expected_types,
+                rows: expected_rows,
+            },
+        ) => {
+            let actual_types = type_line(actual_types);
+            let expected_types = type_line(expected_types);
+            if actual_types != expected_types {
+                return Err(Mismatch::Types {
+                    actual: actual_types,
+                    expected: expected_types,
+                });
+            }
+            compare_rows(actual_rows, expected_rows, |_, actual, expected| {
+                actual == expected
+            })
+        }
+        (DBOutput::StatementComplete(actual), DBOutput::StatementComplete(expected)) => {
+            if actual == expected {
+                Ok(())
+            } else {
+                Err(Mismatch::StatementCount {
+                    actual: *actual,
+                    expected: *expected,
+                })
+            }
+        }
+        _ => {
+            let (actual, expected) = (output_kind(actual), output_kind(expected));
+            if actual == expected {
+                // Neither rows nor statements, which only differ by kind here.
+                Ok(())
+            } else {
+                Err(Mismatch::Kind { actual, expected })
+            }
+        }
+    }
+}
+
+/// Compares rows cell by cell, with `cells_match` called with the column index and both cells.
+fn compare_rows(
+    actual: &[Vec<String>],
+    expected: &[Vec<String>],
+    cells_match: impl Fn(usize, &str, &str) -> bool,
+) -> Result<(), Mismatch> {
+    if actual.len() != expected.len() {
+        return Err(Mismatch::RowCount {
+            actual: actual.len(),
+            expected: expected.len(),
+        });
+    }
+
+    for (row, (actual_row, expected_row)) in actual.iter().zip(expected).enumerate() {
+        if actual_row.len() != expected_row.len() {
+            return Err(Mismatch::ColumnCount {
+                row,
+                actual: actual_row.len(),
+                expected: expected_row.len(),
+            });
+        }
+
+        for (column, (actual_cell, expected_cell)) in
+            actual_row.iter().zip(expected_row).enumerate()
+        {
+            if !cells_match(column, actual_cell, expected_cell) {
+                return Err(Mismatch::Cell {
+                    row,
+                    column,
+                    actual: actual_cell.clone(),
+                    expected: expected_cell.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders column types as in the type line of a sqllogictest query record, e.g. `IT`.
+fn type_line(types: &[ArrowColumnType]) -> String {
+    types.iter().map(ColumnType::to_char).collect()
+}
+
+fn output_kind(output: &DBOutput<ArrowColumnType>) -> &'static str {
+    match output {
+        DBOutput::Rows { .. } => "rows",
+        DBOutput::StatementComplete(_) => "statement",
+        _ => "unsupported output",
+    }
+}
+
+fn floats_match(actual: &str, expected: &str, epsilon: f64) -> bool {
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(a), Ok(b)) => {
+            if a.is_nan() || b.is_nan() {
+                a.is_nan() && b.is_nan()
+            } else if a.is_infinite() || b.is_infinite() {
+                a == b
+            } else {
+                (a - b).abs() <= epsilon * 1f64.max(a.abs()).max(b.abs())
+            }
+        }
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqllogictest::DBOutput;
+
+    use super::{Mismatch, compare_outputs, compare_rows_with_tolerance};
+    use crate::column::ArrowColumnType;
+
+    fn rows(values: &[&[&str]]) -> Vec<Vec<String>> {
+        values
+            .iter()
+            .map(|row| row.iter().map(|v| v.to_string()).collect())
+            .collect()
+    }
+
+    const TYPES: [ArrowColumnType; 2] = [ArrowColumnType::Text, ArrowColumnType::Float];
+
+    #[test]
+    fn float_columns_match_within_tolerance() {
+        let actual = rows(&[&["a", "0.30000000000000004"], &["b", "1000000.1"]]);
+        let expected = rows(&[&["a", "0.3"], &["b", "1000000.2"]]);
+
+        assert_eq!(
+            compare_rows_with_tolerance(&actual, &expected, &TYPES, 1e-6),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn float_columns_outside_tolerance_mismatch() {
+        let actual = rows(&[&["a", "0.31"]]);
+        let expected = rows(&[&["a", "0.3"]]);
+
+        assert_eq!(
+            compare_rows_with_tolerance(&actual, &expected, &TYPES, 1e-6),
+            Err(Mismatch::Cell {
+                row: 0,
+                column: 1,
+                actual: "0.31".to_string(),
+                expected: "0.3".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn special_and_null_floats_compare_exactly() {
+        let actual = rows(&[&["a", "NaN"], &["b", "Infinity"], &["c", "NULL"]]);
+        let expected = actual.clone();
+        assert_eq!(
+            compare_rows_with_tolerance(&actual, &expected, &TYPES, 1e-6),
+            Ok(())
+        );
+
+        let expected = rows(&[&["a", "NaN"], &["b", "-Infinity"], &["c", "NULL"]]);
+        assert!(compare_rows_with_tolerance(&actual, &expected, &TYPES, 1e-6).is_err());
+    }
+
+    #[test]
+    fn non_float_columns_compare_exactly() {
+        let actual = rows(&[&["1.0", "1"]]);
+        let expected = rows(&[&["1", "1"]]);
+
+        assert!(matches!(
+            compare_rows_with_tolerance(&actual, &expected, &TYPES, 1.0),
+            Err(Mismatch::Cell { column: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn shape_differences_are_reported() {
+        assert_eq!(
+            compare_rows_with_tolerance(&rows(&[&["a", "1"]]), &[], &TYPES, 0.0),
+            Err(Mismatch::RowCount {
+                actual: 1,
+                expected: 0
+            })
+        );
+        assert_eq!(
+            compare_rows_with_tolerance(&rows(&[&["a"]]), &rows(&[&["a", "1"]]), &TYPES, 0.0),
+            Err(Mismatch::ColumnCount {
+                row: 0,
+                actual: 1,
+                expected: 2
+            })
+        );
+    }
+
+    fn output(types: &[ArrowColumnType], values: &[&[&str]]) -> DBOutput<ArrowColumnType> {
+        DBOutput::Rows {
+            types: types.to_vec(),
+            rows: rows(values),
+        }
+    }
+
+    #[test]
+    fn matching_outputs_compare_equal() {
+        let actual = output(&TYPES, &[&["a", "1.5"], &["b", "NULL"]]);
+        let expected = output(&TYPES, &[&["a", "1.5"], &["b", "NULL"]]);
+        assert_eq!(compare_outputs(&actual, &expected), Ok(()));
+        assert_eq!(
+            compare_outputs(
+                &DBOutput::StatementComplete(2),
+                &DBOutput::StatementComplete(2)
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn mismatching_outputs_report_first_difference() {
+        let actual = output(&TYPES, &[&["a", "1.5"], &["b", "2"], &["c", "3"]]);
+        let expected = output(&TYPES, &[&["a", "1.5"], &["b", "2.0"], &["d", "3"]]);
+        assert_eq!(
+            compare_outputs(&actual, &expected),
+            Err(Mismatch::Cell {
+                row: 1,
+                column: 1,
+                actual: "2".to_string(),
+                expected: "2.0".to_string(),
+            })
+        );
+
+        let texts = output(
+            &[ArrowColumnType::Text, ArrowColumnType::Text],
+            &[&["a", "1.5"]],
+        );
+        assert_eq!(
+            compare_outputs(&texts, &actual),
+            Err(Mismatch::Types {
+                actual: "TT".to_string(),
+                expected: "TR".to_string(),
+            })
+        );
+        assert_eq!(
+            compare_outputs(&DBOutput::StatementComplete(0), &actual),
+            Err(Mismatch::Kind {
+                actual: "statement",
+                expected: "rows",
+            })
+        );
+        assert_eq!(
+            compare_outputs(
+                &DBOutput::StatementComplete(1),
+                &DBOutput::StatementComplete(2)
+            ),
+            Err(Mismatch::StatementCount {
+                actual: 1,
+                expected: 2
+            })
+        );
+    }
+}
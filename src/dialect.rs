@@ -0,0 +1,65 @@
+use arrow::array::{AsArray, BooleanArray, RecordBatch, Scalar, StringArray};
+use arrow::compute::kernels::boolean::and;
+use arrow::compute::{filter_record_batch, like};
+
+use crate::error::FlightSqlLogicTestError;
+
+/// Known deviations of a server from the Flight SQL specification, worked around by
+/// [`FlightSqlDB`](crate::runner::FlightSqlDB).
+///
+/// Only dialects that can be tested against an in-process server are included, vendor
+/// specific ones should be added once their quirks can be verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ServerDialect {
+    /// Follows the specification, no workarounds are applied.
+    #[default]
+    Generic,
+    /// Compares the schema and table name filters of `CommandGetTables` as exact names
+    /// instead of `LIKE` patterns.
+    ///
+    /// The filters are not sent, instead all tables of the catalog are fetched and the
+    /// patterns are matched client-side. This is slower for servers with many tables.
+    LiteralFilters,
+}
+
+/// Keeps the rows of `batch` whose `column` matches the `LIKE` `pattern`.
+fn filter_like(
+    batch: &RecordBatch,
+    column: &str,
+    pattern: Option<&str>,
+    mask: Option<BooleanArray>,
+) -> Result<Option<BooleanArray>, FlightSqlLogicTestError> {
+    let Some(pattern) = pattern else {
+        return Ok(mask);
+    };
+    let values = batch
+        .column_by_name(column)
+        .ok_or_else(|| format!("Missing column {column}"))?;
+    let values = values
+        .as_string_opt::<i32>()
+        .ok_or_else(|| format!("Column {column} is not a string column"))?;
+    let matches = like(values, &Scalar::new(StringArray::from(vec![pattern])))?;
+    Ok(Some(match mask {
+        Some(mask) => and(&mask, &matches)?,
+        None => matches,
+    }))
+}
+
+/// Filters the result of `CommandGetTables` by the given `LIKE` patterns.
+pub(crate) fn filter_tables(
+    batches: Vec<RecordBatch>,
+    db_schema_filter_pattern: Option<&str>,
+    table_name_filter_pattern: Option<&str>,
+) -> Result<Vec<RecordBatch>, FlightSqlLogicTestError> {
+    batches
+        .into_iter()
+        .map(|batch| {
+            let mask = filter_like(&batch, "db_schema_name", db_schema_filter_pattern, None)?;
+            let mask = filter_like(&batch, "table_name", table_name_filter_pattern, mask)?;
+            Ok(match mask {
+                Some(mask) => filter_record_batch(&batch, &mask)?,
+                None => batch,
+            })
+        })
+        .collect()
+}
@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use arrow::error::ArrowError;
 use arrow_flight::error::FlightError;
+use tonic::Code;
 
 #[derive(Debug, thiserror::Error)]
 pub enum FlightSqlLogicTestError {
@@ -12,10 +16,121 @@ pub enum FlightSqlLogicTestError {
     #[error("Flight SQL error: {0}")]
     FlightSql(#[from] FlightError),
 
+    #[error("Authentication error: {0}")]
+    Auth(String),
+
+    #[error("Ticket of endpoint {endpoint} expired {expired_for:?} before it was fetched")]
+    TicketExpired {
+        endpoint: usize,
+        expired_for: Duration,
+    },
+
+    /// A batch did not match the schema of its result.
+    #[error("Schema mismatch, expected (-) and received (+) fields:\n{diff}")]
+    SchemaMismatch { diff: String },
+
+    #[error("Expected {expected} rows, got {actual}")]
+    UnexpectedRowCount { expected: usize, actual: usize },
+
+    #[error("Output does not match {}:\n{diff}", path.display())]
+    GoldenMismatch { path: PathBuf, diff: String },
+
+    /// A replayed query was never recorded, see [`crate::replay::ReplayFlightSqlDB`].
+    #[error("No recording of `{sql}` at {}", path.display())]
+    MissingRecording { sql: String, path: PathBuf },
+
+    /// Result columns map to the `?` type char, see
+    /// [`crate::runner::FlightSqlDB::run_strict_types`]. Each column is named with its
+    /// arrow type, like `a: Binary`.
+    #[error("Columns without a type char: {}", columns.join(", "))]
+    UntypedColumns { columns: Vec<String> },
+
+    #[error("Failed to execute `{sql}`: {source}")]
+    Statement {
+        sql: String,
+        #[source]
+        source: Box<FlightSqlLogicTestError>,
+    },
+
+    /// A server error reduced to the server's own message, see
+    /// [`FlightSqlLogicTestError::server_message`].
+    #[error("{0}")]
+    Server(String),
+
+    /// The server does not implement a requested feature.
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
+impl FlightSqlLogicTestError {
+    /// Returns the message of the gRPC status the server failed with, without the prefixes
+    /// added by this crate and arrow-flight.
+    ///
+    /// Returns `None` for errors that did not originate from a server status.
+    pub fn server_message(&self) -> Option<String> {
+        match self {
+            Self::FlightSql(FlightError::Tonic(status)) => Some(status.message().to_string()),
+            Self::FlightSql(FlightError::Arrow(ArrowError::IpcError(status)))
+            | Self::Arrow(ArrowError::IpcError(status)) => debug_status_message(status),
+            Self::Statement { source, .. } => source.server_message(),
+            Self::Server(message) => Some(message.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the server failed with an `Unimplemented` gRPC status.
+    pub(crate) fn is_unimplemented(&self) -> bool {
+        match self {
+            Self::FlightSql(FlightError::Tonic(status)) => status.code() == Code::Unimplemented,
+            Self::FlightSql(FlightError::Arrow(ArrowError::IpcError(status)))
+            | Self::Arrow(ArrowError::IpcError(status)) => {
+                status.starts_with("Status { code: Unimplemented,")
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Extracts the message from the `Debug` output of a `tonic::Status`, which is how the Flight
+/// SQL client reports statuses as [`ArrowError::IpcError`].
+fn debug_status_message(status: &str) -> Option<String> {
+    if !status.starts_with("Status {") {
+        return None;
+    }
+    let Some((_, escaped)) = status.split_once(" message: \"") else {
+        // Statuses without a message omit the field.
+        return Some(String::new());
+    };
+
+    let mut message = String::new();
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(message),
+            '\\' => match chars.next()? {
+                'n' => message.push('\n'),
+                'r' => message.push('\r'),
+                't' => message.push('\t'),
+                '0' => message.push('\0'),
+                'u' => {
+                    let code = chars
+                        .by_ref()
+                        .skip(1)
+                        .take_while(|c| *c != '}')
+                        .collect::<String>();
+                    message.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                escaped => message.push(escaped),
+            },
+            c => message.push(c),
+        }
+    }
+    None
+}
+
 impl From<String> for FlightSqlLogicTestError {
     fn from(value: String) -> Self {
         Self::Other(value)
@@ -27,3 +142,67 @@ impl From<&str> for FlightSqlLogicTestError {
         Self::Other(value.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow::error::ArrowError;
+    use arrow_flight::error::FlightError;
+    use tonic::{Code, Status};
+
+    use super::FlightSqlLogicTestError;
+
+    #[test]
+    fn server_message_from_status() {
+        let error = FlightSqlLogicTestError::from(FlightError::Tonic(Box::new(Status::new(
+            Code::InvalidArgument,
+            "table t not found",
+        ))));
+        assert_eq!(error.server_message().as_deref(), Some("table t not found"));
+    }
+
+    #[test]
+    fn unimplemented_from_status_and_debug_output() {
+        let status = Status::unimplemented("no statements");
+        let debug_error =
+            FlightSqlLogicTestError::from(ArrowError::IpcError(format!("{status:?}")));
+        let error = FlightSqlLogicTestError::from(FlightError::Tonic(Box::new(status)));
+        assert!(error.is_unimplemented());
+        assert!(debug_error.is_unimplemented());
+
+        let status = Status::internal("Unimplemented");
+        let error = FlightSqlLogicTestError::from(ArrowError::IpcError(format!("{status:?}")));
+        assert!(!error.is_unimplemented());
+    }
+
+    #[test]
+    fn server_message_from_status_debug_output() {
+        let status = Status::new(Code::Internal, "column \"a\" is\tambiguous\n\u{1b}");
+        let error = FlightSqlLogicTestError::from(ArrowError::IpcError(format!("{status:?}")));
+        assert_eq!(
+            error.server_message().as_deref(),
+            Some("column \"a\" is\tambiguous\n\u{1b}")
+        );
+
+        let status = Status::new(Code::Internal, "");
+        let error = FlightSqlLogicTestError::from(ArrowError::IpcError(format!("{status:?}")));
+        assert_eq!(error.server_message().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn server_message_of_statement_error() {
+        let error = FlightSqlLogicTestError::Statement {
+            sql: "create table t".to_string(),
+            source: Box::new(
+                FlightError::Tonic(Box::new(Status::already_exists("t exists"))).into(),
+            ),
+        };
+        assert_eq!(error.server_message().as_deref(), Some("t exists"));
+    }
+
+    #[test]
+    fn no_server_message_for_client_errors() {
+        let error = FlightSqlLogicTestError::from(ArrowError::IpcError("bad frame".to_string()));
+        assert_eq!(error.server_message(), None);
+        assert_eq!(FlightSqlLogicTestError::from("oops").server_message(), None);
+    }
+}
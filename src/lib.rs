@@ -1,3 +1,15 @@
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod builder;
 pub mod column;
+pub mod compare;
+pub mod dialect;
 pub mod error;
+pub mod metadata;
+pub mod output;
+pub mod replay;
 pub mod runner;
+
+#[cfg(test)]
+mod test_util;
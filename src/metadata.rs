@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Int32Type, Int64Type, Schema, UInt32Type};
+use arrow_flight::sql::{SqlInfo, SqlSupportedTransaction, XdbcDataType};
+
+use crate::error::FlightSqlLogicTestError;
+
+/// Columns of a `CommandGetXdbcTypeInfo` result holding XDBC data type codes.
+const XDBC_TYPE_CODE_COLUMNS: [&str; 2] = ["data_type", "sql_data_type"];
+
+/// Returns the readable name of an XDBC data type code, e.g. `INTEGER` for `4`.
+///
+/// Codes unknown to the protocol are rendered as the number itself.
+pub fn xdbc_type_name(code: i32) -> String {
+    match XdbcDataType::try_from(code) {
+        Ok(data_type) => data_type
+            .as_str_name()
+            .trim_start_matches("XDBC_")
+            .to_string(),
+        Err(_) => code.to_string(),
+    }
+}
+
+/// Replaces the XDBC data type codes of a `CommandGetXdbcTypeInfo` result with their names.
+///
+/// The `data_type` and `sql_data_type` columns become `Utf8` columns; every other column is
+/// passed through untouched.
+pub fn map_xdbc_type_names(batch: &RecordBatch) -> Result<RecordBatch, FlightSqlLogicTestError> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if XDBC_TYPE_CODE_COLUMNS.contains(&field.name().as_str())
+            && column.data_type() == &DataType::Int32
+        {
+            let names: StringArray = column
+                .as_primitive::<Int32Type>()
+                .iter()
+                .map(|code| code.map(xdbc_type_name))
+                .collect();
+            fields.push(Arc::new(
+                Field::new(field.name(), DataType::Utf8, field.is_nullable())
+                    .with_metadata(field.metadata().clone()),
+            ));
+            columns.push(Arc::new(names) as ArrayRef);
+        } else {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// `GetSqlInfo` codes queried for [`ServerCapabilities`].
+pub(crate) const CAPABILITY_INFOS: [SqlInfo; 10] = [
+    SqlInfo::FlightSqlServerName,
+    SqlInfo::FlightSqlServerVersion,
+    SqlInfo::FlightSqlServerReadOnly,
+    SqlInfo::FlightSqlServerSql,
+    SqlInfo::FlightSqlServerSubstrait,
+    SqlInfo::FlightSqlServerTransaction,
+    SqlInfo::FlightSqlServerCancel,
+    SqlInfo::FlightSqlServerBulkIngestion,
+    SqlInfo::FlightSqlServerIngestTransactionsSupported,
+    SqlInfo::FlightSqlServerStatementTimeout,
+];
+
+/// Capabilities a server reports through `GetSqlInfo`, see
+/// [`FlightSqlDB::capabilities`](crate::runner::FlightSqlDB::capabilities).
+///
+/// Each field is `None` if the server did not report it, or reported it with an unexpected
+/// value type. Flight SQL has no info for statement updates or prepared statements, which
+/// every server is expected to implement; `sql` tells whether SQL is accepted at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub server_name: Option<String>,
+    pub server_version: Option<String>,
+    pub read_only: Option<bool>,
+    /// Whether SQL queries, updates and prepared statements are supported. Older servers
+    /// may support SQL without reporting it.
+    pub sql: Option<bool>,
+    /// Whether Substrait plans are supported.
+    pub substrait: Option<bool>,
+    /// Support of the transaction and savepoint actions. Servers without it may still
+    /// support SQL `BEGIN` and `COMMIT` statements.
+    pub transactions: Option<SqlSupportedTransaction>,
+    /// Whether queries can be cancelled.
+    pub cancel: Option<bool>,
+    /// Whether bulk ingestion is supported.
+    pub bulk_ingestion: Option<bool>,
+    /// Whether bulk ingestion supports transactions.
+    pub ingest_transactions: Option<bool>,
+    /// Timeout of prepared statement handles, zero for none.
+    pub statement_timeout: Option<Duration>,
+}
+
+impl ServerCapabilities {
+    /// Reads the capabilities from the batches of a `GetSqlInfo` result.
+    pub(crate) fn from_batches(batches: &[RecordBatch]) -> Result<Self, FlightSqlLogicTestError> {
+        let mut capabilities = Self::default();
+        for batch in batches {
+            let (Some(names), Some(values)) = (
+                batch
+                    .column_by_name("info_name")
+                    .and_then(|col| col.as_primitive_opt::<UInt32Type>()),
+                batch
+                    .column_by_name("value")
+                    .and_then(|col| col.as_union_opt()),
+            ) else {
+                return Err(
+                    format!("Unexpected GetSqlInfo result schema: {}", batch.schema()).into(),
+                );
+            };
+
+            for row in 0..batch.num_rows() {
+                let child = values.child(values.type_id(row));
+                let offset = values.value_offset(row);
+                if !child.is_valid(offset) {
+                    continue;
+                }
+                let string = || {
+                    child
+                        .as_string_opt::<i32>()
+                        .map(|values| values.value(offset).to_string())
+                };
+                let bool = || child.as_boolean_opt().map(|values| values.value(offset));
+                let int = || match child.data_type() {
+                    DataType::Int32 => {
+                        Some(i64::from(child.as_primitive::<Int32Type>().value(offset)))
+                    }
+                    DataType::Int64 => Some(child.as_primitive::<Int64Type>().value(offset)),
+                    _ => None,
+                };
+
+                let Ok(info) = SqlInfo::try_from(names.value(row) as i32) else {
+                    continue;
+                };
+                match info {
+                    SqlInfo::FlightSqlServerName => capabilities.server_name = string(),
+                    SqlInfo::FlightSqlServerVersion => capabilities.server_version = string(),
+                    SqlInfo::FlightSqlServerReadOnly => capabilities.read_only = bool(),
+                    SqlInfo::FlightSqlServerSql => capabilities.sql = bool(),
+                    SqlInfo::FlightSqlServerSubstrait => capabilities.substrait = bool(),
+                    SqlInfo::FlightSqlServerTransaction => {
+                        capabilities.transactions = int()
+                            .and_then(|value| i32::try_from(value).ok())
+                            .and_then(|value| SqlSupportedTransaction::try_from(value).ok());
+                    }
+                    SqlInfo::FlightSqlServerCancel => capabilities.cancel = bool(),
+                    SqlInfo::FlightSqlServerBulkIngestion => capabilities.bulk_ingestion = bool(),
+                    SqlInfo::FlightSqlServerIngestTransactionsSupported => {
+                        capabilities.ingest_transactions = bool();
+                    }
+                    SqlInfo::FlightSqlServerStatementTimeout => {
+                        capabilities.statement_timeout = int()
+                            .and_then(|millis| u64::try_from(millis).ok())
+                            .map(Duration::from_millis);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(capabilities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Array, AsArray, Int32Array, RecordBatch, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use super::{map_xdbc_type_names, xdbc_type_name};
+
+    #[test]
+    fn known_codes_map_to_names() {
+        assert_eq!(xdbc_type_name(4), "INTEGER");
+        assert_eq!(xdbc_type_name(12), "VARCHAR");
+        assert_eq!(xdbc_type_name(-5), "BIGINT");
+        assert_eq!(xdbc_type_name(1234), "1234");
+    }
+
+    #[test]
+    fn maps_type_code_columns_only() {
+        let schema = Schema::new(vec![
+            Field::new("type_name", DataType::Utf8, false),
+            Field::new("data_type", DataType::Int32, false),
+            Field::new("column_size", DataType::Int32, true),
+            Field::new("sql_data_type", DataType::Int32, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec!["int", "varchar"])),
+                Arc::new(Int32Array::from(vec![4, 12])),
+                Arc::new(Int32Array::from(vec![Some(10), None])),
+                Arc::new(Int32Array::from(vec![4, 12])),
+            ],
+        )
+        .unwrap();
+
+        let mapped = map_xdbc_type_names(&batch).unwrap();
+
+        let data_type = mapped.column(1).as_string::<i32>();
+        assert_eq!(data_type.value(0), "INTEGER");
+        assert_eq!(data_type.value(1), "VARCHAR");
+        let sql_data_type = mapped.column(3).as_string::<i32>();
+        assert_eq!(sql_data_type.value(1), "VARCHAR");
+        assert_eq!(mapped.column(2).data_type(), &DataType::Int32);
+        assert!(mapped.column(2).is_null(1));
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use arrow::util::pretty::pretty_format_batches_with_schema;
+use md5::{Digest, Md5};
+use sqllogictest::{ColumnType, DBOutput};
+
+use crate::error::FlightSqlLogicTestError;
+
+/// Environment variable that makes [`FlightSqlDB::run_golden`](crate::runner::FlightSqlDB::run_golden)
+/// write expected files instead of comparing against them, when set to `true` or `1`.
+pub const ENV_BLESS: &str = "FLIGHTSQL_BLESS";
+
+/// Renders `output` as a canonical text block, like the result of a sqllogictest record.
+///
+/// Rows are preceded by their type chars and a `----` separator line, with the cells of each
+/// row joined by a single space. Statements render as `statement count <n>`.
+pub fn dboutput_to_string<T: ColumnType>(output: &DBOutput<T>) -> String {
+    let mut text = String::new();
+    match output {
+        DBOutput::Rows { types, rows } => {
+            let types = types.iter().map(ColumnType::to_char).collect::<String>();
+            writeln!(text, "{types}").unwrap();
+            writeln!(text, "----").unwrap();
+            for row in rows {
+                writeln!(text, "{}", row.join(" ")).unwrap();
+            }
+        }
+        DBOutput::StatementComplete(count) => writeln!(text, "statement count {count}").unwrap(),
+        _ => writeln!(text, "unsupported output").unwrap(),
+    }
+    text
+}
+
+/// Like [`dboutput_to_string`], but replaces rows holding more than `threshold` values with a
+/// single `<n> values hashing to <md5>` line, the form sqllogictest compares against when its
+/// runner has a hash threshold set. A `threshold` of 0 never hashes.
+///
+/// The threshold counts values, not rows, as in sqllogictest and DuckDB: the number of rows
+/// times the number of types, which is also the `<n>` of the hash line. This matches the
+/// runner even when rows are wider than their types, e.g. after
+/// [`ConvertOptions::with_expand_column`].
+///
+/// The hash is the lowercase hex MD5 of every value followed by `\n`, in row-major order, e.g.
+/// the rows `1 a` and `2 NULL` hash `"1\na\n2\nNULL\n"`. Rows should be sorted like the
+/// runner sorts them before hashing, e.g. with [`FlightSqlDB::set_sort_mode`].
+///
+/// [`FlightSqlDB::set_sort_mode`]: crate::runner::FlightSqlDB::set_sort_mode
+/// [`ConvertOptions::with_expand_column`]: crate::column::ConvertOptions::with_expand_column
+pub fn dboutput_to_hashed_string<T: ColumnType>(output: &DBOutput<T>, threshold: usize) -> String {
+    let DBOutput::Rows { types, rows } = output else {
+        return dboutput_to_string(output);
+    };
+    let values = rows.len() * types.len();
+    if threshold == 0 || values <= threshold {
+        return dboutput_to_string(output);
+    }
+
+    let mut md5 = Md5::new();
+    for value in rows.iter().flatten() {
+        md5.update(value.as_bytes());
+        md5.update(b"\n");
+    }
+    let types = types.iter().map(ColumnType::to_char).collect::<String>();
+    format!(
+        "{types}\n----\n{values} values hashing to {:x}\n",
+        md5.finalize()
+    )
+}
+
+/// Limits applied to tables rendered by [`pretty_format_with_options`]. Unlimited by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrettyOptions {
+    max_rows: Option<usize>,
+    max_cols: Option<usize>,
+}
+
+impl PrettyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders only the first `max_rows` rows.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Renders only the first `max_cols` columns.
+    pub fn with_max_cols(mut self, max_cols: usize) -> Self {
+        self.max_cols = Some(max_cols);
+        self
+    }
+}
+
+/// Renders `batches` as an aligned table like arrow's pretty printer, truncated to the limits
+/// of `options`.
+///
+/// A truncated table is followed by a line like `... showing 10 of 1000 rows, 3 of 8
+/// columns`, naming only the truncated dimensions.
+pub fn pretty_format_with_options(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    options: &PrettyOptions,
+) -> Result<String, FlightSqlLogicTestError> {
+    let total_rows = batches.iter().map(RecordBatch::num_rows).sum::<usize>();
+    let total_cols = schema.fields.len();
+    let rows = options
+        .max_rows
+        .map_or(total_rows, |max| max.min(total_rows));
+    let cols = options
+        .max_cols
+        .map_or(total_cols, |max| max.min(total_cols));
+
+    let indices = (0..cols).collect::<Vec<_>>();
+    let mut remaining = rows;
+    let mut shown = Vec::new();
+    for batch in batches {
+        let len = batch.num_rows().min(remaining);
+        shown.push(batch.slice(0, len).project(&indices)?);
+        remaining -= len;
+    }
+    let schema = Arc::new(schema.project(&indices)?);
+    let mut text = pretty_format_batches_with_schema(schema, &shown)?.to_string();
+
+    let mut truncated = Vec::new();
+    if rows < total_rows {
+        truncated.push(format!("{rows} of {total_rows} rows"));
+    }
+    if cols < total_cols {
+        truncated.push(format!("{cols} of {total_cols} columns"));
+    }
+    if !truncated.is_empty() {
+        write!(text, "\n... showing {}", truncated.join(", ")).unwrap();
+    }
+    Ok(text)
+}
+
+/// Returns a line-by-line diff of `expected` and `actual`, or `None` if they are equal.
+///
+/// Lines are compared by position: matching lines are prefixed with two spaces, differing
+/// ones are shown as a `-` expected line followed by a `+` actual line.
+pub(crate) fn line_diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    let mut diff = String::new();
+    for index in 0..expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(expected), Some(actual)) if expected == actual => {
+                writeln!(diff, "  {expected}").unwrap();
+            }
+            (expected, actual) => {
+                if let Some(expected) = expected {
+                    writeln!(diff, "-{expected}").unwrap();
+                }
+                if let Some(actual) = actual {
+                    writeln!(diff, "+{actual}").unwrap();
+                }
+            }
+        }
+    }
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use sqllogictest::DBOutput;
+
+    use super::{
+        PrettyOptions, dboutput_to_hashed_string, dboutput_to_string, line_diff,
+        pretty_format_with_options,
+    };
+    use crate::column::ArrowColumnType;
+
+    #[test]
+    fn renders_rows_with_types() {
+        let output = DBOutput::Rows {
+            types: vec![ArrowColumnType::Integer, ArrowColumnType::Text],
+            rows: vec![
+                vec!["1".to_string(), "a".to_string()],
+                vec!["2".to_string(), "NULL".to_string()],
+            ],
+        };
+        assert_eq!(dboutput_to_string(&output), "IT\n----\n1 a\n2 NULL\n");
+    }
+
+    #[test]
+    fn renders_statement_counts() {
+        let output = DBOutput::<ArrowColumnType>::StatementComplete(3);
+        assert_eq!(dboutput_to_string(&output), "statement count 3\n");
+    }
+
+    #[test]
+    fn hashes_rows_above_threshold() {
+        let output = DBOutput::Rows {
+            types: vec![ArrowColumnType::Integer, ArrowColumnType::Text],
+            rows: vec![
+                vec!["1".to_string(), "a".to_string()],
+                vec!["2".to_string(), "NULL".to_string()],
+            ],
+        };
+        assert_eq!(
+            dboutput_to_hashed_string(&output, 3),
+            "IT\n----\n4 values hashing to f81e8ec4542cf691ed1411e41a4cac45\n"
+        );
+        assert_eq!(
+            dboutput_to_hashed_string(&output, 4),
+            dboutput_to_string(&output)
+        );
+        assert_eq!(
+            dboutput_to_hashed_string(&output, 0),
+            dboutput_to_string(&output)
+        );
+    }
+
+    #[test]
+    fn hashed_value_count_follows_the_types() {
+        let output = DBOutput::Rows {
+            types: vec![ArrowColumnType::Text],
+            rows: vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["c".to_string(), "d".to_string()],
+            ],
+        };
+        assert_eq!(
+            dboutput_to_hashed_string(&output, 1),
+            "T\n----\n2 values hashing to 47ece2e49e5c0333677fc34e044d8257\n"
+        );
+        assert_eq!(
+            dboutput_to_hashed_string(&output, 2),
+            dboutput_to_string(&output)
+        );
+    }
+
+    #[test]
+    fn pretty_tables_are_truncated() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batches = [vec![1, 2], vec![3]].map(|values| {
+            let column = Arc::new(Int32Array::from(values));
+            RecordBatch::try_new(schema.clone(), vec![column.clone(), column]).unwrap()
+        });
+        let format = |options: PrettyOptions| {
+            pretty_format_with_options(&schema, &batches, &options).unwrap()
+        };
+
+        assert_eq!(
+            format(PrettyOptions::new().with_max_rows(1).with_max_cols(1)),
+            "+---+\n| a |\n+---+\n| 1 |\n+---+\n... showing 1 of 3 rows, 1 of 2 columns"
+        );
+        assert_eq!(
+            format(PrettyOptions::new().with_max_rows(3)),
+            "+---+---+\n| a | b |\n+---+---+\n| 1 | 1 |\n| 2 | 2 |\n| 3 | 3 |\n+---+---+"
+        );
+        assert_eq!(
+            format(PrettyOptions::new().with_max_rows(0)),
+            "+---+---+\n| a | b |\n+---+---+\n+---+---+\n... showing 0 of 3 rows"
+        );
+    }
+
+    #[test]
+    fn diffs_lines_by_position() {
+        assert_eq!(line_diff("a\nb\n", "a\nb\n"), None);
+        assert_eq!(
+            line_diff("I\n----\n1\n2\n", "I\n----\n1\n3\n4\n").unwrap(),
+            "  I\n  ----\n  1\n-2\n+3\n+4\n"
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use sqllogictest::{AsyncDB, DBOutput};
+
+use crate::column::{ArrowColumnType, ConvertOptions};
+use crate::error::FlightSqlLogicTestError;
+use crate::runner::{FlightSqlDB, ResultSortMode, result_to_output};
+
+/// Key of the custom metadata holding the SQL text in recorded Arrow IPC files.
+pub const SQL_METADATA_KEY: &str = "sql";
+
+/// Runs queries on a [`FlightSqlDB`] and records their results to a directory, to be served
+/// later by a [`ReplayFlightSqlDB`].
+///
+/// Each query is stored under the 64-bit FNV-1a hash of its SQL text as lowercase hex:
+/// a result as the Arrow IPC file `<hash>.arrow`, with the SQL text in its custom metadata
+/// under [`SQL_METADATA_KEY`], and a failure as the text file `<hash>.err`, holding the byte
+/// length of the SQL text on its first line, then the SQL text and a newline, then the error
+/// message. Recording a query again replaces its previous recording. The SQL text lets a
+/// replay tell apart queries whose hashes collide.
+///
+/// Only failures reported by the server are recorded, as [`FlightSqlLogicTestError::Server`]
+/// or [`FlightSqlLogicTestError::Statement`]; enable
+/// [`FlightSqlDB::with_raw_error_messages`] to get them. Other errors, like transport,
+/// authentication or timeout failures, are returned without recording anything.
+///
+/// Schema mismatch retries are not applied while recording, the first attempt is recorded.
+#[derive(Debug)]
+pub struct RecordingFlightSqlDB {
+    db: FlightSqlDB,
+    dir: PathBuf,
+}
+
+impl RecordingFlightSqlDB {
+    pub fn new(db: FlightSqlDB, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            db,
+            dir: dir.into(),
+        }
+    }
+
+    /// Returns the db queries are run on.
+    pub fn into_inner(self) -> FlightSqlDB {
+        self.db
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncDB for RecordingFlightSqlDB {
+    type Error = FlightSqlLogicTestError;
+    type ColumnType = ArrowColumnType;
+
+    async fn run(&mut self, sql: &str) -> Result<DBOutput<Self::ColumnType>, Self::Error> {
+        let result = self.db.execute_for_run(sql).await;
+        if let Ok(_)
+        | Err(FlightSqlLogicTestError::Server(_) | FlightSqlLogicTestError::Statement { .. }) =
+            &result
+        {
+            write_recording(&self.dir, sql, &result)?;
+        }
+        let (schema, batches) = result?;
+        self.db.to_output(&schema, batches)
+    }
+
+    async fn shutdown(&mut self) {
+        self.db.shutdown().await;
+    }
+
+    fn engine_name(&self) -> &str {
+        self.db.engine_name()
+    }
+}
+
+/// Serves queries from the recordings of a [`RecordingFlightSqlDB`] without a server.
+///
+/// Results are converted like by [`FlightSqlDB`], with the convert options and sort mode set
+/// here. Recorded failures are returned as [`FlightSqlLogicTestError::Server`] holding the
+/// recorded message. A query that was never recorded fails with
+/// [`FlightSqlLogicTestError::MissingRecording`], so suites drifting from their recordings
+/// fail instead of silently passing. So does a query whose recording file holds other SQL
+/// text, e.g. of a query with the same hash.
+#[derive(Debug)]
+pub struct ReplayFlightSqlDB {
+    engine_name: String,
+    dir: PathBuf,
+    convert_options: ConvertOptions,
+    sort_mode: ResultSortMode,
+}
+
+impl ReplayFlightSqlDB {
+    pub fn new(engine_name: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            engine_name: engine_name.into(),
+            dir: dir.into(),
+            convert_options: ConvertOptions::default(),
+            sort_mode: ResultSortMode::NoSort,
+        }
+    }
+
+    /// Sets the options used to convert replayed results, see
+    /// [`FlightSqlDB::with_convert_options`].
+    pub fn with_convert_options(mut self, options: ConvertOptions) -> Self {
+        self.convert_options = options;
+        self
+    }
+
+    /// Sets how replayed rows are sorted, see [`FlightSqlDB::set_sort_mode`].
+    pub fn with_sort_mode(mut self, sort_mode: impl Into<ResultSortMode>) -> Self {
+        self.sort_mode = sort_mode.into();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncDB for ReplayFlightSqlDB {
+    type Error = FlightSqlLogicTestError;
+    type ColumnType = ArrowColumnType;
+
+    async fn run(&mut self, sql: &str) -> Result<DBOutput<Self::ColumnType>, Self::Error> {
+        let (schema, batches) = read_recording(&self.dir, sql)?;
+        result_to_output(&schema, batches, &self.convert_options, self.sort_mode)
+    }
+
+    async fn shutdown(&mut self) {}
+
+    fn engine_name(&self) -> &str {
+        &self.engine_name
+    }
+}
+
+/// Returns the path of the recording of `sql` without extension.
+fn recording_path(dir: &Path, sql: &str) -> PathBuf {
+    // FNV-1a, as std hashers are not guaranteed to be stable across Rust releases.
+    let hash = sql.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    dir.join(format!("{hash:016x}"))
+}
+
+fn write_recording(
+    dir: &Path,
+    sql: &str,
+    result: &Result<(Schema, Vec<RecordBatch>), FlightSqlLogicTestError>,
+) -> Result<(), FlightSqlLogicTestError> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let path = recording_path(dir, sql);
+    let (arrow_path, error_path) = (path.with_extension("arrow"), path.with_extension("err"));
+    let (result_path, stale_path) = match result {
+        Ok(_) => (arrow_path, error_path),
+        Err(_) => (error_path, arrow_path),
+    };
+    let write_error = |e: &dyn std::fmt::Display| {
+        FlightSqlLogicTestError::Other(format!("Failed to write {}: {e}", result_path.display()))
+    };
+
+    match result {
+        Ok((schema, batches)) => {
+            let file = File::create(&result_path).map_err(|e| write_error(&e))?;
+            let mut writer = FileWriter::try_new(file, schema).map_err(|e| write_error(&e))?;
+            writer.write_metadata(SQL_METADATA_KEY, sql);
+            for batch in batches {
+                writer.write(batch).map_err(|e| write_error(&e))?;
+            }
+            writer.finish().map_err(|e| write_error(&e))?;
+        }
+        Err(e) => {
+            let contents = format!("{}\n{sql}\n{e}", sql.len());
+            fs::write(&result_path, contents).map_err(|e| write_error(&e))?;
+        }
+    }
+    match fs::remove_file(&stale_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {e}", stale_path.display()).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn read_recording(
+    dir: &Path,
+    sql: &str,
+) -> Result<(Schema, Vec<RecordBatch>), FlightSqlLogicTestError> {
+    let path = recording_path(dir, sql);
+    let error_path = path.with_extension("err");
+    if let Ok(contents) = fs::read_to_string(&error_path) {
+        return Err(match recorded_error_message(&contents, sql) {
+            Some(message) => FlightSqlLogicTestError::Server(message.to_string()),
+            None => FlightSqlLogicTestError::MissingRecording {
+                sql: sql.to_string(),
+                path: error_path,
+            },
+        });
+    }
+
+    let result_path = path.with_extension("arrow");
+    let file = match File::open(&result_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(FlightSqlLogicTestError::MissingRecording {
+                sql: sql.to_string(),
+                path: result_path,
+            });
+        }
+        Err(e) => return Err(format!("Failed to read {}: {e}", result_path.display()).into()),
+    };
+    let reader = FileReader::try_new(file, None)?;
+    if reader
+        .custom_metadata()
+        .get(SQL_METADATA_KEY)
+        .map(String::as_str)
+        != Some(sql)
+    {
+        return Err(FlightSqlLogicTestError::MissingRecording {
+            sql: sql.to_string(),
+            path: result_path,
+        });
+    }
+    let schema = reader.schema().as_ref().clone();
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// Returns the message of a recorded failure, or `None` if it was recorded for other SQL
+/// text, see [`RecordingFlightSqlDB`].
+fn recorded_error_message<'a>(contents: &'a str, sql: &str) -> Option<&'a str> {
+    let (len, rest) = contents.split_once('\n')?;
+    let len = len.parse::<usize>().ok()?;
+    if rest.get(..len)? != sql {
+        return None;
+    }
+    rest[len..].strip_prefix('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use sqllogictest::{AsyncDB, DBOutput, SortMode};
+
+    use super::{RecordingFlightSqlDB, ReplayFlightSqlDB, recording_path};
+    use crate::auth::TokenProvider;
+    use crate::column::ArrowColumnType;
+    use crate::error::FlightSqlLogicTestError;
+    use crate::runner::FlightSqlDB;
+    use crate::test_util::{TempDir, TestFlightSqlService};
+
+    type Output = Result<DBOutput<ArrowColumnType>, FlightSqlLogicTestError>;
+
+    fn rows(output: Output) -> Vec<Vec<String>> {
+        match output {
+            Ok(DBOutput::Rows { rows, .. }) => rows,
+            _ => panic!("expected rows"),
+        }
+    }
+
+    fn expect_error(output: Output) -> FlightSqlLogicTestError {
+        let Err(error) = output else {
+            panic!("expected an error");
+        };
+        error
+    }
+
+    #[tokio::test]
+    async fn recorded_results_and_errors_are_replayed() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = [vec![3, 1], vec![2]].map(|values| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))]).unwrap()
+        });
+        let service = TestFlightSqlService::new().with_result(
+            "select a from t",
+            schema,
+            vec![batches.to_vec()],
+        );
+        let endpoint = service.serve().await;
+        let db = FlightSqlDB::builder("demo-db", endpoint)
+            .build()
+            .await
+            .unwrap()
+            .with_raw_error_messages(true);
+        let dir = TempDir::new("replay");
+        let mut recording = RecordingFlightSqlDB::new(db, dir.path());
+
+        let recorded = rows(recording.run("select a from t").await);
+        let recorded_error = expect_error(recording.run("select b from t").await);
+        assert!(matches!(recorded_error, FlightSqlLogicTestError::Server(_)));
+
+        let mut replay = ReplayFlightSqlDB::new("demo-db", dir.path());
+        assert_eq!(rows(replay.run("select a from t").await), recorded);
+        let error = expect_error(replay.run("select b from t").await);
+        assert_eq!(error.to_string(), recorded_error.to_string());
+
+        let mut sorted =
+            ReplayFlightSqlDB::new("demo-db", dir.path()).with_sort_mode(SortMode::RowSort);
+        assert_eq!(
+            rows(sorted.run("select a from t").await),
+            vec![vec!["1"], vec!["2"], vec!["3"]]
+        );
+
+        let error = expect_error(replay.run("select c from t").await);
+        assert!(
+            matches!(&error, FlightSqlLogicTestError::MissingRecording { sql, .. } if sql == "select c from t"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn recordings_of_other_sql_are_missing() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![1]))])
+            .unwrap();
+        let service =
+            TestFlightSqlService::new().with_result("select a from t", schema, vec![vec![batch]]);
+        let endpoint = service.serve().await;
+        let db = FlightSqlDB::builder("demo-db", endpoint)
+            .build()
+            .await
+            .unwrap()
+            .with_raw_error_messages(true);
+        let dir = TempDir::new("replay-collision");
+        let mut recording = RecordingFlightSqlDB::new(db, dir.path());
+        rows(recording.run("select a from t").await);
+        expect_error(recording.run("select b from t").await);
+
+        // Stand in for hash collisions: the files of `select c` and `select d` hold the
+        // recordings of `select a` and `select b`.
+        let mut replay = ReplayFlightSqlDB::new("demo-db", dir.path());
+        for (recorded, colliding, extension) in [
+            ("select a from t", "select c from t", "arrow"),
+            ("select b from t", "select d from t", "err"),
+        ] {
+            let colliding_path = recording_path(dir.path(), colliding).with_extension(extension);
+            std::fs::rename(
+                recording_path(dir.path(), recorded).with_extension(extension),
+                &colliding_path,
+            )
+            .unwrap();
+
+            let error = expect_error(replay.run(colliding).await);
+            assert!(
+                matches!(&error, FlightSqlLogicTestError::MissingRecording { sql, path }
+                    if sql == colliding && *path == colliding_path),
+                "{error}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn only_server_failures_are_recorded() {
+        let endpoint = TestFlightSqlService::new().serve().await;
+        let db = FlightSqlDB::builder("demo-db", endpoint)
+            .with_token_provider(TokenProvider::new(|| async {
+                Err::<String, _>("expired refresh token")
+            }))
+            .build()
+            .await
+            .unwrap()
+            .with_raw_error_messages(true);
+        let dir = TempDir::new("replay-auth");
+        let mut recording = RecordingFlightSqlDB::new(db, dir.path());
+
+        let error = expect_error(recording.run("select a from t").await);
+        assert!(matches!(error, FlightSqlLogicTestError::Auth(_)), "{error}");
+        assert!(!dir.path().exists());
+    }
+}
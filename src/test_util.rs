@@ -0,0 +1,651 @@
+//! In-process Flight SQL server used by the unit tests.
+
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::writer::{DictionaryHandling as IpcDictionaryHandling, IpcWriteOptions};
+use arrow_flight::{
+    FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest, HandshakeResponse, Ticket,
+    encode::{DictionaryHandling, FlightDataEncoderBuilder},
+    flight_descriptor::DescriptorType,
+    flight_service_server::{FlightService, FlightServiceServer},
+    sql::{
+        Command, CommandGetSqlInfo, CommandGetTables, CommandStatementQuery,
+        CommandStatementSubstraitPlan, ProstMessageExt, SqlInfo, TicketStatementQuery,
+        metadata::{SqlInfoData, SqlInfoDataBuilder},
+        server::FlightSqlService,
+    },
+};
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use hyper_util::rt::TokioIo;
+use prost::Message;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::codegen::Service;
+use tonic::{
+    Request, Response, Status, Streaming,
+    metadata::{MetadataMap, MetadataValue},
+    transport::{Server, Uri},
+};
+
+/// The result served for a query: one list of batches per endpoint.
+#[derive(Clone)]
+pub(crate) struct TestResult {
+    pub(crate) schema: SchemaRef,
+    pub(crate) endpoints: Vec<Vec<RecordBatch>>,
+}
+
+#[derive(Default)]
+struct TestState {
+    results: Mutex<HashMap<String, TestResult>>,
+    endpoint_expiration: Mutex<Option<SystemTime>>,
+    omit_flight_info_schema: AtomicBool,
+    dictionary_deltas: AtomicBool,
+    reject_statement_queries: AtomicBool,
+    wrong_flight_info_schemas: Mutex<Option<(SchemaRef, usize)>>,
+    tables: Mutex<Vec<(String, String)>>,
+    tables_requests: Mutex<Vec<CommandGetTables>>,
+    statement_requests: Mutex<Vec<CommandStatementQuery>>,
+    substrait_schema: Mutex<Option<SchemaRef>>,
+    sql_info: Mutex<Option<SqlInfoData>>,
+    sql_info_calls: AtomicUsize,
+    requests: Mutex<Vec<(&'static str, MetadataMap)>>,
+    do_get_calls: AtomicUsize,
+}
+
+/// A Flight SQL service answering queries with canned results.
+#[derive(Clone, Default)]
+pub(crate) struct TestFlightSqlService {
+    state: Arc<TestState>,
+}
+
+impl TestFlightSqlService {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the result returned for `sql`.
+    pub(crate) fn with_result(
+        self,
+        sql: impl Into<String>,
+        schema: SchemaRef,
+        endpoints: Vec<Vec<RecordBatch>>,
+    ) -> Self {
+        self.state
+            .results
+            .lock()
+            .unwrap()
+            .insert(sql.into(), TestResult { schema, endpoints });
+        self
+    }
+
+    /// Registers the result returned for the path descriptor `path`.
+    pub(crate) fn with_path_result(
+        self,
+        path: &[&str],
+        schema: SchemaRef,
+        endpoints: Vec<Vec<RecordBatch>>,
+    ) -> Self {
+        self.with_result(path_key(path), schema, endpoints)
+    }
+
+    /// Sets the expiration time of every endpoint returned.
+    pub(crate) fn with_endpoint_expiration(self, expiration: SystemTime) -> Self {
+        *self.state.endpoint_expiration.lock().unwrap() = Some(expiration);
+        self
+    }
+
+    /// Leaves the schema of every returned `FlightInfo` empty, so it is only sent with the
+    /// batches.
+    pub(crate) fn without_flight_info_schema(self) -> Self {
+        self.state
+            .omit_flight_info_schema
+            .store(true, Ordering::SeqCst);
+        self
+    }
+
+    /// Sends dictionaries with every batch, as deltas when a batch only appends values to the
+    /// dictionary of the previous batch, instead of hydrating them.
+    pub(crate) fn with_dictionary_deltas(self) -> Self {
+        self.state.dictionary_deltas.store(true, Ordering::SeqCst);
+        self
+    }
+
+    /// Rejects statement queries as unimplemented, like servers only supporting prepared
+    /// statements.
+    pub(crate) fn without_statement_queries(self) -> Self {
+        self.state
+            .reject_statement_queries
+            .store(true, Ordering::SeqCst);
+        self
+    }
+
+    /// Advertises `schema` instead of the actual result schema in the next `calls` returned
+    /// `FlightInfo`s.
+    pub(crate) fn with_wrong_flight_info_schema(self, schema: SchemaRef, calls: usize) -> Self {
+        *self.state.wrong_flight_info_schemas.lock().unwrap() = Some((schema, calls));
+        self
+    }
+
+    /// Registers tables of catalog `main` as `(db_schema, table)` names.
+    ///
+    /// Like some real servers, `CommandGetTables` filters are compared as exact names rather
+    /// than as `LIKE` patterns.
+    pub(crate) fn with_tables(self, tables: &[(&str, &str)]) -> Self {
+        *self.state.tables.lock().unwrap() = tables
+            .iter()
+            .map(|(db_schema, table)| (db_schema.to_string(), table.to_string()))
+            .collect();
+        self
+    }
+
+    /// Accepts Substrait plans, describing every plan as `schema`.
+    ///
+    /// Without a schema, Substrait plans are rejected as unimplemented.
+    pub(crate) fn with_substrait_schema(self, schema: SchemaRef) -> Self {
+        *self.state.substrait_schema.lock().unwrap() = Some(schema);
+        self
+    }
+
+    /// Answers `GetSqlInfo` with `sql_info`. Without it, `GetSqlInfo` is rejected as
+    /// unimplemented.
+    pub(crate) fn with_sql_info(self, sql_info: SqlInfoData) -> Self {
+        *self.state.sql_info.lock().unwrap() = Some(sql_info);
+        self
+    }
+
+    /// Number of `GetSqlInfo` calls received so far.
+    pub(crate) fn sql_info_calls(&self) -> usize {
+        self.state.sql_info_calls.load(Ordering::SeqCst)
+    }
+
+    /// `CommandGetTables` requests received so far.
+    pub(crate) fn tables_requests(&self) -> Vec<CommandGetTables> {
+        self.state.tables_requests.lock().unwrap().clone()
+    }
+
+    /// `CommandStatementQuery` requests received so far.
+    pub(crate) fn statement_requests(&self) -> Vec<CommandStatementQuery> {
+        self.state.statement_requests.lock().unwrap().clone()
+    }
+
+    /// Number of `do_get` calls received so far.
+    pub(crate) fn do_get_calls(&self) -> usize {
+        self.state.do_get_calls.load(Ordering::SeqCst)
+    }
+
+    /// Bytes of the binary `key` header received by each query RPC, in order.
+    pub(crate) fn received_binary_header(&self, key: &str) -> Vec<(&'static str, Option<Vec<u8>>)> {
+        self.state
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(rpc, metadata)| {
+                let value = metadata
+                    .get_bin(key)
+                    .map(|value| value.to_bytes().unwrap().to_vec());
+                (*rpc, value)
+            })
+            .collect()
+    }
+
+    /// Values of the `key` header received by each query RPC, in order.
+    pub(crate) fn received_header(&self, key: &str) -> Vec<(&'static str, Option<String>)> {
+        self.state
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(rpc, metadata)| {
+                let value = metadata
+                    .get(key)
+                    .map(|value| value.to_str().unwrap().to_string());
+                (*rpc, value)
+            })
+            .collect()
+    }
+
+    fn record_request(&self, rpc: &'static str, metadata: &MetadataMap) {
+        self.state
+            .requests
+            .lock()
+            .unwrap()
+            .push((rpc, metadata.clone()));
+    }
+
+    fn result(&self, sql: &str) -> Result<TestResult, Status> {
+        self.state
+            .results
+            .lock()
+            .unwrap()
+            .get(sql)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("no result for query: {sql}")))
+    }
+
+    /// Returns the `FlightInfo` of the result registered under `key`.
+    fn flight_info(&self, key: &str) -> Result<FlightInfo, Status> {
+        let result = self.result(key)?;
+        let mut schema = result.schema.clone();
+        if let Some((wrong_schema, calls)) = self
+            .state
+            .wrong_flight_info_schemas
+            .lock()
+            .unwrap()
+            .as_mut()
+            && *calls > 0
+        {
+            *calls -= 1;
+            schema = wrong_schema.clone();
+        }
+        let mut flight_info = FlightInfo::new();
+        if !self.state.omit_flight_info_schema.load(Ordering::SeqCst) {
+            flight_info = flight_info
+                .try_with_schema(&schema)
+                .map_err(|e| Status::internal(e.to_string()))?;
+        }
+        let expiration = *self.state.endpoint_expiration.lock().unwrap();
+        for index in 0..result.endpoints.len() {
+            let ticket = TicketStatementQuery {
+                statement_handle: statement_handle(index, key).into(),
+            };
+            let mut endpoint =
+                FlightEndpoint::new().with_ticket(Ticket::new(ticket.as_any().encode_to_vec()));
+            endpoint.expiration_time = expiration.map(|expiration| {
+                let since_epoch = expiration.duration_since(UNIX_EPOCH).unwrap();
+                prost_types::Timestamp {
+                    seconds: since_epoch.as_secs() as i64,
+                    nanos: since_epoch.subsec_nanos() as i32,
+                }
+            });
+            flight_info = flight_info.with_endpoint(endpoint);
+        }
+        Ok(flight_info)
+    }
+
+    /// Serves this service on a random local port and returns its endpoint.
+    pub(crate) async fn serve(&self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("test server should bind");
+        let addr = listener.local_addr().expect("test server should have addr");
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(FlightServiceServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .expect("test server should serve");
+        });
+
+        format!("http://{addr}")
+    }
+}
+
+/// The key under which the result of a path descriptor is registered, like a query.
+fn path_key<S: AsRef<str>>(path: &[S]) -> String {
+    let path = path.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    format!("path:{}", path.join("/"))
+}
+
+/// Encodes the endpoint index and query into a statement handle.
+fn statement_handle(index: usize, sql: &str) -> Vec<u8> {
+    format!("{index}:{sql}").into_bytes()
+}
+
+#[tonic::async_trait]
+impl FlightSqlService for TestFlightSqlService {
+    type FlightService = Self;
+
+    /// Accepts any credentials and returns the token `handshake-token`.
+    async fn do_handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<
+        Response<Pin<Box<dyn Stream<Item = Result<HandshakeResponse, Status>> + Send>>>,
+        Status,
+    > {
+        self.record_request("handshake", request.metadata());
+        let response = HandshakeResponse {
+            protocol_version: 0,
+            payload: Default::default(),
+        };
+        let responses: Pin<Box<dyn Stream<Item = _> + Send>> =
+            Box::pin(stream::iter(vec![Ok(response)]));
+        let mut response = Response::new(responses);
+        response.metadata_mut().insert(
+            "authorization",
+            MetadataValue::from_static("Bearer handshake-token"),
+        );
+        Ok(response)
+    }
+
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state
+            .tables_requests
+            .lock()
+            .unwrap()
+            .push(query.clone());
+        let schema = query.clone().into_builder().schema();
+        let ticket = Ticket::new(query.as_any().encode_to_vec());
+        let flight_info = FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(FlightEndpoint::new().with_ticket(ticket));
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let matches = |filter: &Option<String>, name: &str| {
+            filter.as_ref().is_none_or(|filter| filter == name)
+        };
+        let mut builder = CommandGetTables::default().into_builder();
+        for (db_schema, table) in self.state.tables.lock().unwrap().iter() {
+            if matches(&query.db_schema_filter_pattern, db_schema)
+                && matches(&query.table_name_filter_pattern, table)
+            {
+                builder
+                    .append("main", db_schema, table, "TABLE", &Schema::empty())
+                    .map_err(|e| Status::internal(e.to_string()))?;
+            }
+        }
+        let schema = builder.schema();
+        let batch = builder
+            .build()
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let stream = FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(stream::iter(vec![Ok(batch)]))
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.record_request("get_flight_info", request.metadata());
+        self.state
+            .statement_requests
+            .lock()
+            .unwrap()
+            .push(query.clone());
+        if self.state.reject_statement_queries.load(Ordering::SeqCst) {
+            return Err(Status::unimplemented("use prepared statements"));
+        }
+        Ok(Response::new(self.flight_info(&query.query)?))
+    }
+
+    async fn get_flight_info_fallback(
+        &self,
+        _cmd: Command,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.record_request("get_flight_info", request.metadata());
+        let descriptor = request.get_ref();
+        if descriptor.r#type() != DescriptorType::Path {
+            return Err(Status::unimplemented("unsupported command"));
+        }
+        Ok(Response::new(
+            self.flight_info(&path_key(&descriptor.path))?,
+        ))
+    }
+
+    async fn get_flight_info_substrait_plan(
+        &self,
+        _query: CommandStatementSubstraitPlan,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.record_request("get_flight_info", request.metadata());
+        let schema = self
+            .state
+            .substrait_schema
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| Status::unimplemented("Substrait plans are not supported"))?;
+        let flight_info = FlightInfo::new()
+            .try_with_schema(&schema)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        self.state.do_get_calls.fetch_add(1, Ordering::SeqCst);
+        self.record_request("do_get", request.metadata());
+
+        let handle = String::from_utf8(ticket.statement_handle.to_vec())
+            .map_err(|_| Status::invalid_argument("statement handle is not utf-8"))?;
+        let (index, sql) = handle
+            .split_once(':')
+            .ok_or_else(|| Status::invalid_argument("malformed statement handle"))?;
+        let index: usize = index
+            .parse()
+            .map_err(|_| Status::invalid_argument("malformed endpoint index"))?;
+        let result = self.result(sql)?;
+        let batches = result
+            .endpoints
+            .get(index)
+            .cloned()
+            .ok_or_else(|| Status::not_found("no such endpoint"))?;
+
+        let mut encoder = FlightDataEncoderBuilder::new().with_schema(result.schema);
+        if self.state.dictionary_deltas.load(Ordering::SeqCst) {
+            encoder = encoder
+                .with_dictionary_handling(DictionaryHandling::Resend)
+                .with_options(
+                    IpcWriteOptions::default()
+                        .with_dictionary_handling(IpcDictionaryHandling::Delta),
+                );
+        }
+        let stream = encoder
+            .build(stream::iter(batches.into_iter().map(Ok)))
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn get_flight_info_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.state.sql_info_calls.fetch_add(1, Ordering::SeqCst);
+        if self.state.sql_info.lock().unwrap().is_none() {
+            return Err(Status::unimplemented("no sql info"));
+        }
+        let ticket = Ticket::new(query.as_any().encode_to_vec());
+        let flight_info = FlightInfo::new()
+            .try_with_schema(SqlInfoDataBuilder::schema())
+            .map_err(|e| Status::internal(e.to_string()))?
+            .with_endpoint(FlightEndpoint::new().with_ticket(ticket));
+        Ok(Response::new(flight_info))
+    }
+
+    async fn do_get_sql_info(
+        &self,
+        query: CommandGetSqlInfo,
+        _request: Request<Ticket>,
+    ) -> Result<Response<<Self as FlightService>::DoGetStream>, Status> {
+        let batch = self
+            .state
+            .sql_info
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or_else(|| Status::unimplemented("no sql info"))?
+            .record_batch(query.info)
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let stream = FlightDataEncoderBuilder::new()
+            .build(stream::iter(vec![Ok(batch)]))
+            .map_err(Status::from);
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+/// A TCP connector whose connections can be broken and restored on command.
+///
+/// While failing, new connections are refused and every connection made before the failure
+/// errors on its next read or write.
+#[derive(Clone, Default)]
+pub(crate) struct FlakyConnector {
+    state: Arc<FlakyState>,
+}
+
+#[derive(Default)]
+struct FlakyState {
+    failing: AtomicBool,
+    generation: AtomicUsize,
+    connects: AtomicUsize,
+}
+
+impl FlakyConnector {
+    /// Breaks all open connections and refuses new ones until [`FlakyConnector::recover`].
+    pub(crate) fn fail(&self) {
+        self.state.generation.fetch_add(1, Ordering::SeqCst);
+        self.state.failing.store(true, Ordering::SeqCst);
+    }
+
+    /// Accepts new connections again; connections broken before stay broken.
+    pub(crate) fn recover(&self) {
+        self.state.failing.store(false, Ordering::SeqCst);
+    }
+
+    /// Number of connection attempts so far.
+    pub(crate) fn connects(&self) -> usize {
+        self.state.connects.load(Ordering::SeqCst)
+    }
+}
+
+impl Service<Uri> for FlakyConnector {
+    type Response = TokioIo<FlakyStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let state = self.state.clone();
+        Box::pin(async move {
+            state.connects.fetch_add(1, Ordering::SeqCst);
+            if state.failing.load(Ordering::SeqCst) {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "connector is failing",
+                ));
+            }
+            let authority = uri.authority().expect("endpoint should have an authority");
+            let stream = TcpStream::connect(authority.as_str()).await?;
+            let generation = state.generation.load(Ordering::SeqCst);
+            Ok(TokioIo::new(FlakyStream {
+                stream,
+                state,
+                generation,
+            }))
+        })
+    }
+}
+
+/// A connection of a [`FlakyConnector`].
+pub(crate) struct FlakyStream {
+    stream: TcpStream,
+    state: Arc<FlakyState>,
+    generation: usize,
+}
+
+impl FlakyStream {
+    fn check(&self) -> io::Result<()> {
+        if self.state.generation.load(Ordering::SeqCst) == self.generation {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "connection broken by the connector",
+            ))
+        }
+    }
+}
+
+impl AsyncRead for FlakyStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.check()?;
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for FlakyStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.check()?;
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.check()?;
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// A uniquely named directory under the system temp dir, removed with all its contents on
+/// drop, also when the test fails.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(prefix: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, Ordering::SeqCst);
+        let name = format!("{prefix}-{}-{id}", std::process::id());
+        Self(std::env::temp_dir().join(name))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}